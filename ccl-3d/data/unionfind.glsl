@@ -0,0 +1,48 @@
+// the union-find shared by the shaders which join labels.
+// the including shader defines LABEL(index) as the label of an element in a coherent buffer,
+// a root is the element labeled with its own index and negative labels are not part of any set.
+
+// follows the labels up to the root, the element labeled with its own index.
+// labels only ever decrease, so a stale read ends at a former root
+// which still leads to the current one.
+int findRoot(uint index) {
+    int v = LABEL(index);
+    if (v < 0) {
+        return v;
+    }
+    int w = int(index);
+    while (v != w) {
+        w = v;
+        v = LABEL(v);
+    }
+    return v;
+}
+
+// points the elements on the path from `index` directly at `root`
+void compressPath(uint index, int root) {
+    int v = int(index);
+    while (v > root) {
+        v = atomicMin(LABEL(v), root);
+    }
+}
+
+// links the larger root to the smaller one. when another invocation has linked
+// the larger root in the meantime, atomicMin tells where it points now
+// and the union is retried from there.
+void unite(uint i0, uint i1) {
+    int v0 = findRoot(i0);
+    int v1 = findRoot(i1);
+    while (v0 != v1) {
+        int lower = min(v0, v1);
+        int upper = max(v0, v1);
+        int previous = atomicMin(LABEL(upper), lower);
+        if (previous == upper) {
+            break;
+        }
+        v0 = findRoot(lower);
+        v1 = findRoot(previous);
+    }
+    int root = findRoot(i0);
+    compressPath(i0, root);
+    compressPath(i1, root);
+}
@@ -0,0 +1,153 @@
+#[macro_use]
+extern crate filum;
+use filum::{Context, BufferViewBuilder, PipelineBuilder, DispatchBuilder};
+
+mod options;
+mod shader;
+mod volume;
+
+use options::Options;
+use volume::Volume;
+
+extern crate image;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+use std::time::Instant;
+
+// push constant of merge.comp
+const AXIS_X: u32 = 0;
+const AXIS_Z: u32 = 1;
+
+// 3D extension of ccl-simple.
+// columns along y are scanned first, then merged along x within every slice
+// and finally the slices are merged along z.
+fn main() {
+    let options = Options::from_args();
+    let shader_dir = options.shader_dir.as_deref();
+    println!("loading volume");
+    let volume = match options.raw {
+        Some(dimensions) => Volume::from_raw(&options.inputs[0], dimensions),
+        None => {
+            let paths = match options.inputs.as_slice() {
+                [dir] if dir.is_dir() => {
+                    let paths = volume::slices_in(dir).unwrap_or_else(|err| {
+                        eprintln!("failed to list {}: {}", dir.display(), err);
+                        process::exit(1);
+                    });
+                    if paths.is_empty() {
+                        eprintln!("no PNG slices in {}", dir.display());
+                        process::exit(1);
+                    }
+                    paths
+                },
+                paths => {
+                    let mut paths = paths.to_vec();
+                    paths.sort();
+                    paths
+                },
+            };
+            Volume::from_slices(&paths)
+        },
+    };
+    let volume = volume.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let (width, height, depth) = (volume.width, volume.height, volume.depth);
+    let len = width * height * depth;
+    println!("{}x{}x{} voxels", width, height, depth);
+    let context = Context::new().unwrap();
+    let view = BufferViewBuilder::new(&context)
+        .bind_array::<i32>(len)
+        .build()
+        .unwrap();
+    let buffer = view.buffer();
+    let column = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::COLUMN.code(shader_dir))
+        .specialization(constants!(width as u32, height as u32, depth as u32))
+        .build()
+        .unwrap();
+    let merge = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::MERGE.code(shader_dir))
+        .specialization(constants!(width as u32, height as u32, depth as u32, options.connectivity))
+        .build()
+        .unwrap();
+    let relabel = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::RELABEL.code(shader_dir))
+        .build()
+        .unwrap();
+    let mut instant = Instant::now();
+    let binding = view.binding();
+    binding.update_array(|slice| {
+        input(slice, &volume, options.threshold);
+        instant = Instant::now();
+    });
+    println!("column");
+    let dispatch = DispatchBuilder::new(&column)
+        .workgroup_count(width, depth, 1)
+        .build()
+        .unwrap();
+    dispatch.dispatch();
+    println!("merge");
+    // strips of columns within every slice
+    let mut step_index = 0;
+    while (1 << step_index) < width {
+        let stride = 2 << step_index;
+        let n = (width - (1 << step_index)).div_ceil(stride);
+        let dispatch = DispatchBuilder::new(&merge)
+            .workgroup_count(n, depth, 1)
+            .push_constants(constants!(step_index as u32, AXIS_X))
+            .build()
+            .unwrap();
+        dispatch.dispatch();
+        step_index += 1;
+    }
+    // every pair of adjacent slices
+    if depth > 1 {
+        let dispatch = DispatchBuilder::new(&merge)
+            .workgroup_count(width, height, depth - 1)
+            .push_constants(constants!(0u32, AXIS_Z))
+            .build()
+            .unwrap();
+        dispatch.dispatch();
+    }
+    println!("relabel");
+    relabel.dispatch(len);
+    binding.fetch_array(|slice| {
+        println!("done {:?}", instant.elapsed());
+        if let Err(err) = output(slice, &options) {
+            eprintln!("failed to save output: {}", err);
+            process::exit(1);
+        }
+    });
+}
+
+fn input(slice: &mut [i32], volume: &Volume, threshold: u8) {
+    for (index, (elem, &voxel)) in slice.iter_mut().zip(&volume.voxels).enumerate() {
+        *elem = if voxel > threshold { index as i32 } else { -1 };
+    }
+}
+
+fn output(slice: &[i32], options: &Options) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(&options.output)?);
+    for label in slice {
+        writer.write_all(&label.to_le_bytes())?;
+    }
+    writer.flush()?;
+    println!("labels saved as {}", options.output.display());
+    let mut counts: BTreeMap<i32, u64> = BTreeMap::new();
+    for &label in slice.iter().filter(|&&label| label >= 0) {
+        *counts.entry(label).or_insert(0) += 1;
+    }
+    let mut writer = BufWriter::new(File::create(&options.counts)?);
+    writeln!(writer, "label,voxels")?;
+    for (label, count) in &counts {
+        writeln!(writer, "{},{}", label, count)?;
+    }
+    writer.flush()?;
+    println!("{} components, counts saved as {}", counts.len(), options.counts.display());
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+usage: filum-example-ccl-3d [OPTIONS] <INPUT>...
+
+Labels the connected components of a volume given as a stack of image slices
+or as a raw file of 8-bit voxels. The slices are sorted by file name and
+a directory stands for all the PNG files in it.
+
+options:
+        --raw <WxHxD>           reads INPUT as raw 8-bit voxels of the given dimensions
+    -t, --threshold <T>         voxels above T are labeled [default: 0]
+    -c, --connectivity <N>      voxel connectivity: 6 or 26 [default: 6]
+    -o, --output <PATH>         labeled volume as raw little-endian i32,
+                                -1 for the background [default: labels.raw]
+        --counts <PATH>         voxel count of every component as CSV [default: counts.csv]
+        --shader-dir <DIR>      loads <DIR>/*.comp.spv instead of the embedded shaders
+    -h, --help                  prints this message
+";
+
+pub struct Options {
+    pub inputs: Vec<PathBuf>,
+    pub raw: Option<(usize, usize, usize)>,
+    pub threshold: u8,
+    pub connectivity: u32,
+    pub output: PathBuf,
+    pub counts: PathBuf,
+    pub shader_dir: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        let mut options = Options {
+            inputs: Vec::new(),
+            raw: None,
+            threshold: 0,
+            connectivity: 6,
+            output: PathBuf::from("labels.raw"),
+            counts: PathBuf::from("counts.csv"),
+            shader_dir: None,
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--raw" => options.raw = Some(parse_dimensions(&value(&mut args, &arg))),
+                "-t" | "--threshold" => options.threshold = value(&mut args, &arg)
+                    .parse()
+                    .unwrap_or_else(|_| fail("threshold must be in 0-255")),
+                "-c" | "--connectivity" => options.connectivity = match value(&mut args, &arg).as_str() {
+                    "6" => 6,
+                    "26" => 26,
+                    other => fail(&format!("invalid connectivity {}", other)),
+                },
+                "-o" | "--output" => options.output = PathBuf::from(value(&mut args, &arg)),
+                "--counts" => options.counts = PathBuf::from(value(&mut args, &arg)),
+                "--shader-dir" => options.shader_dir = Some(PathBuf::from(value(&mut args, &arg))),
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ if arg.starts_with('-') => fail(&format!("unknown option {}", arg)),
+                _ => options.inputs.push(PathBuf::from(arg)),
+            }
+        }
+        if options.inputs.is_empty() {
+            fail("no input is given");
+        }
+        if options.raw.is_some() && options.inputs.len() != 1 {
+            fail("--raw takes exactly one input");
+        }
+        options
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, name: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| fail(&format!("{} requires a value", name)))
+}
+
+fn parse_dimensions(dimensions: &str) -> (usize, usize, usize) {
+    let values: Vec<usize> = dimensions.split('x')
+        .map(|value| value.parse().unwrap_or_else(|_| fail(&format!("invalid dimensions {}", dimensions))))
+        .collect();
+    match values.as_slice() {
+        &[width, height, depth] if width * height * depth > 0 => (width, height, depth),
+        _ => fail(&format!("invalid dimensions {}", dimensions)),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// 8-bit voxels stored slice by slice, row by row
+pub struct Volume {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub voxels: Vec<u8>,
+}
+
+impl Volume {
+    // reads raw 8-bit voxels of the given dimensions
+    pub fn from_raw(path: &Path, (width, height, depth): (usize, usize, usize)) -> Result<Self, String> {
+        if width == 0 || height == 0 || depth == 0 {
+            return Err(format!("invalid dimensions {}x{}x{}", width, height, depth));
+        }
+        let voxels = fs::read(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        if voxels.len() != width * height * depth {
+            return Err(format!("{} has {} bytes while {}x{}x{} voxels are expected",
+                path.display(), voxels.len(), width, height, depth));
+        }
+        Ok(Volume { width, height, depth, voxels })
+    }
+
+    // stacks the gray levels of the given images, which must share the same dimensions
+    pub fn from_slices(paths: &[PathBuf]) -> Result<Self, String> {
+        if paths.is_empty() {
+            return Err("no slices".to_string());
+        }
+        let mut volume = Volume { width: 0, height: 0, depth: paths.len(), voxels: Vec::new() };
+        for (index, path) in paths.iter().enumerate() {
+            let slice = image::open(path)
+                .map_err(|err| format!("failed to open {}: {}", path.display(), err))?
+                .to_luma8();
+            let (width, height) = (slice.width() as usize, slice.height() as usize);
+            if index == 0 {
+                volume.width = width;
+                volume.height = height;
+                volume.voxels.reserve(width * height * paths.len());
+            } else if (width, height) != (volume.width, volume.height) {
+                return Err(format!("{} is {}x{} while the first slice is {}x{}",
+                    path.display(), width, height, volume.width, volume.height));
+            }
+            volume.voxels.extend_from_slice(&slice.into_raw());
+        }
+        Ok(volume)
+    }
+}
+
+// lists the PNG files in `dir` sorted by file name
+pub fn slices_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")));
+    paths.sort();
+    Ok(paths)
+}
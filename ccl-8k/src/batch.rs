@@ -0,0 +1,78 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::classes;
+use crate::options::Classes;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "tif", "tiff", "tga", "gif"];
+
+// an image decoded for labeling, `path` is none for the embedded sample
+pub struct Image {
+    pub path: Option<PathBuf>,
+    pub img: image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>,
+    pub classes: Option<Vec<u32>>,
+}
+
+impl Image {
+    pub fn new(path: Option<PathBuf>, img: image::DynamicImage, mode: Option<Classes>) -> Self {
+        let classes = mode.map(|mode| {
+            let (classes, num_classes) = classes::from_image(&img, mode);
+            info!("{} classes", num_classes);
+            classes
+        });
+        Image { path, img: img.to_rgba8(), classes }
+    }
+}
+
+// the images of a directory in the order of their file names
+pub fn images(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut images = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_image = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        if is_image {
+            images.push(path);
+        }
+    }
+    images.sort();
+    Ok(images)
+}
+
+// the images of a directory, or the paths listed one per line in a file.
+// empty lines and lines starting with # are skipped.
+pub fn inputs(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if path.is_dir() {
+        return images(path);
+    }
+    let list = fs::read_to_string(path)?;
+    let inputs = list.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect();
+    Ok(inputs)
+}
+
+// decodes the images on another thread in the order of `paths`.
+// the channel has no capacity, so that the next image is decoded while the current one
+// is being labeled but no further, images of 8K take more than 100MB each.
+pub fn decode(paths: Vec<PathBuf>, mode: Option<Classes>) -> mpsc::Receiver<Result<Image, String>> {
+    let (sender, receiver) = mpsc::sync_channel(0);
+    thread::spawn(move || {
+        for path in paths {
+            let image = match image::open(&path) {
+                Ok(img) => Ok(Image::new(Some(path), img, mode)),
+                Err(err) => Err(format!("failed to open {}: {}", path.display(), err)),
+            };
+            if sender.send(image).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use image::{DynamicImage, GenericImageView};
+
+use crate::options::Classes;
+
+// returns the class ID of every pixel and the number of distinct classes.
+// with `Classes::Palette`, IDs are given to colors in the order of appearance.
+// with `Classes::Mask`, the gray level of an 8 or 16-bit image is the ID.
+pub fn from_image(img: &DynamicImage, mode: Classes) -> (Vec<u32>, usize) {
+    match mode {
+        Classes::Palette => {
+            let mut ids: HashMap<[u8; 3], u32> = HashMap::new();
+            let classes = img.pixels()
+                .map(|(_, _, image::Rgba(data))| {
+                    let next = ids.len() as u32;
+                    *ids.entry([data[0], data[1], data[2]]).or_insert(next)
+                })
+                .collect();
+            (classes, ids.len())
+        },
+        Classes::Mask => {
+            let classes: Vec<u32> = match img {
+                DynamicImage::ImageLuma16(buffer) => buffer.pixels()
+                    .map(|image::Luma([value])| *value as u32)
+                    .collect(),
+                _ => img.to_luma8()
+                    .pixels()
+                    .map(|image::Luma([value])| *value as u32)
+                    .collect(),
+            };
+            let mut present = vec![false; 1 << 16];
+            classes.iter().for_each(|&class| present[class as usize] = true);
+            let num_classes = present.iter().filter(|&&present| present).count();
+            (classes, num_classes)
+        },
+    }
+}
@@ -0,0 +1,77 @@
+use std::cmp::Reverse;
+
+use crate::options::Options;
+
+// set on the area of components which touch the image border, same as area.comp
+pub const BORDER: u32 = 0x8000_0000;
+
+// push constant of filter.comp
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Filter {
+    min_area: u32,
+    remove_border: u32,
+    largest_area: u32,
+    largest_root: u32,
+}
+
+impl Filter {
+    // `areas` is indexed by the root of every component as filled by area.comp,
+    // and is required with `--keep-largest` only
+    pub fn new(options: &Options, areas: Option<&[u32]>) -> Self {
+        let mut filter = Filter {
+            min_area: options.min_area,
+            remove_border: options.remove_border as u32,
+            largest_area: 0,
+            largest_root: u32::MAX,
+        };
+        if let Some(n) = options.keep_largest {
+            // the largest ones first, then the ones with the smaller root
+            let mut candidates: Vec<(u32, u32)> = areas.unwrap().iter()
+                .enumerate()
+                .filter(|&(root, &entry)| entry != 0 && filter.keeps(entry, root as u32))
+                .map(|(root, &entry)| (entry & !BORDER, root as u32))
+                .collect();
+            if candidates.len() > n {
+                candidates.sort_by_key(|&(area, root)| (Reverse(area), root));
+                let (area, root) = candidates[n - 1];
+                filter.largest_area = area;
+                filter.largest_root = root;
+            }
+        }
+        filter
+    }
+
+    // same as filter.comp
+    pub fn keeps(&self, entry: u32, root: u32) -> bool {
+        let area = entry & !BORDER;
+        let border = entry & BORDER != 0;
+        area >= self.min_area
+            && !(self.remove_border != 0 && border)
+            && (area > self.largest_area || (area == self.largest_area && root <= self.largest_root))
+    }
+}
+
+pub fn is_enabled(options: &Options) -> bool {
+    options.min_area > 0 || options.keep_largest.is_some() || options.remove_border
+}
+
+// filters labels on the CPU, as area.comp and filter.comp do on the GPU
+pub fn apply(labels: &mut [i32], width: usize, height: usize, options: &Options) {
+    let mut areas = vec![0u32; labels.len()];
+    for (index, &label) in labels.iter().enumerate() {
+        if label >= 0 {
+            areas[label as usize] += 1;
+            let (x, y) = (index % width, index / width);
+            if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+                areas[label as usize] |= BORDER;
+            }
+        }
+    }
+    let filter = Filter::new(options, Some(&areas));
+    for label in labels.iter_mut().filter(|label| **label >= 0) {
+        if !filter.keeps(areas[*label as usize], *label as u32) {
+            *label = -1;
+        }
+    }
+}
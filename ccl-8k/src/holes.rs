@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::reference;
+
+pub struct Holes {
+    // number of holes of every component
+    pub counts: HashMap<i32, u32>,
+    // the component around every hole, by the root of the hole
+    enclosing: HashMap<i32, i32>,
+    background: Vec<i32>,
+}
+
+// the background is labeled with the connectivity dual to the one of the components,
+// so that the diagonal gaps of 4-connected components are not holes and vice versa
+pub fn dual(connectivity: u32) -> u32 {
+    if connectivity == 8 { 4 } else { 8 }
+}
+
+// the foreground rule inverted, after filtering, as the input of the column, merge
+// and relabel passes which label the background with the dual connectivity
+pub fn background(slice: &mut [i32], labels: &[i32]) {
+    for (index, (elem, &label)) in slice.iter_mut().zip(labels).enumerate() {
+        *elem = if label < 0 { index as i32 } else { -1 };
+    }
+}
+
+// labels the background on the CPU, then finds the holes
+pub fn find_on_cpu(labels: &[i32], width: usize, height: usize, connectivity: u32) -> Holes {
+    let mask: Vec<bool> = labels.iter().map(|&label| label < 0).collect();
+    let background = reference::label(&mask, width, height, dual(connectivity), None);
+    find(labels, background, width, height)
+}
+
+// finds the background regions which do not touch the image border.
+// such a region is a hole of the component around it, which is the only component bordering
+// the region that is not inside the region itself. a component is inside the region
+// the pixel right above its first pixel belongs to. a region bordered by several components
+// which are not inside it, such as the gap between two facing C shapes, is no hole.
+pub fn find(labels: &[i32], background: Vec<i32>, width: usize, height: usize) -> Holes {
+    let mut is_open: HashMap<i32, bool> = HashMap::new();
+    // the components 4-adjacent to every region
+    let mut bordering: HashMap<i32, HashSet<i32>> = HashMap::new();
+    // the region around every component, none for the ones on the top edge
+    let mut outside: HashMap<i32, Option<i32>> = HashMap::new();
+    for (index, &region) in background.iter().enumerate() {
+        let (x, y) = (index % width, index / width);
+        if region >= 0 {
+            let is_border = x == 0 || y == 0 || x + 1 == width || y + 1 == height;
+            *is_open.entry(region).or_insert(false) |= is_border;
+        } else {
+            let above = if y > 0 { Some(background[index - width]) } else { None };
+            outside.entry(labels[index]).or_insert(above);
+        }
+        // the pairs with the left and the upper neighbors, the others are seen from their side
+        let mut pairs = vec![];
+        if x > 0 {
+            pairs.push(index - 1);
+        }
+        if y > 0 {
+            pairs.push(index - width);
+        }
+        for neighbor in pairs {
+            match (region, background[neighbor]) {
+                (region, other) if region >= 0 && other < 0 => {
+                    bordering.entry(region).or_default().insert(labels[neighbor]);
+                },
+                (region, other) if region < 0 && other >= 0 => {
+                    bordering.entry(other).or_default().insert(labels[index]);
+                },
+                _ => {},
+            }
+        }
+    }
+    let mut counts = HashMap::new();
+    let mut enclosing = HashMap::new();
+    for (&region, &is_open) in &is_open {
+        if is_open {
+            continue;
+        }
+        let mut around = bordering[&region].iter()
+            .filter(|&component| outside[component] != Some(region));
+        if let (Some(&component), None) = (around.next(), around.next()) {
+            *counts.entry(component).or_insert(0) += 1;
+            enclosing.insert(region, component);
+        }
+    }
+    Holes { counts, enclosing, background }
+}
+
+impl Holes {
+    pub fn len(&self) -> usize {
+        self.enclosing.len()
+    }
+
+    // labels the pixels of every hole with the component around it
+    pub fn fill(&self, labels: &mut [i32]) {
+        for (label, region) in labels.iter_mut().zip(&self.background) {
+            if let Some(&component) = self.enclosing.get(region) {
+                *label = component;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // finds the holes of 4-connected components drawn with . for the background
+    // and the class of every foreground pixel otherwise
+    fn holes(rows: &[&str]) -> (Vec<i32>, Holes) {
+        let (width, height) = (rows[0].len(), rows.len());
+        let cells = rows.concat();
+        let foreground: Vec<bool> = cells.chars().map(|c| c != '.').collect();
+        let classes: Vec<u32> = cells.chars().map(|c| c.to_digit(10).unwrap_or(0)).collect();
+        let labels = reference::label(&foreground, width, height, 4, Some(&classes));
+        let holes = find_on_cpu(&labels, width, height, 4);
+        (labels, holes)
+    }
+
+    #[test]
+    fn ring() {
+        let (mut labels, holes) = holes(&[
+            ".....",
+            ".111.",
+            ".1.1.",
+            ".111.",
+            ".....",
+        ]);
+        assert_eq!(holes.len(), 1);
+        assert_eq!(holes.counts[&6], 1);
+        holes.fill(&mut labels);
+        assert_eq!(labels[12], 6);
+    }
+
+    // the gap between two C shapes of different classes which face each other
+    // is enclosed by neither of them
+    #[test]
+    fn facing_c_shapes() {
+        let (mut labels, holes) = holes(&[
+            "......",
+            ".1122.",
+            ".1..2.",
+            ".1122.",
+            "......",
+        ]);
+        assert_ne!(labels[7], labels[9]);
+        assert_eq!(holes.len(), 0);
+        assert!(holes.counts.is_empty());
+        let unfilled = labels.clone();
+        holes.fill(&mut labels);
+        assert_eq!(labels, unfilled);
+    }
+
+    // a component inside a hole does not keep the hole from being one,
+    // and the hole is not filled over the component
+    #[test]
+    fn island() {
+        let (mut labels, holes) = holes(&[
+            ".......",
+            ".11111.",
+            ".1...1.",
+            ".1.1.1.",
+            ".1...1.",
+            ".11111.",
+            ".......",
+        ]);
+        assert_eq!(holes.len(), 1);
+        assert_eq!(holes.counts[&8], 1);
+        assert!(!holes.counts.contains_key(&24));
+        holes.fill(&mut labels);
+        assert_eq!(labels[16], 8);
+        assert_eq!(labels[24], 24);
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// saves the labels losslessly, the format is chosen by the extension of the path.
+// PNG and TIFF hold label + 1 as unsigned 16-bit gray levels so that the background is 0.
+// when a label does not fit, TIFF holds 32-bit gray levels and PNG 8-bit RGBA
+// whose channels are the bytes of label + 1 in little-endian order.
+// PNG is meant for compacted labels, the options reject it without --compact.
+// NumPy `.npy` and any other extension hold the labels as little-endian i32, -1 for the background.
+pub fn save(path: &Path, labels: &[i32], width: usize, height: usize) -> io::Result<()> {
+    let extension = path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let is_16bit = labels.iter().all(|&label| label < u16::MAX as i32);
+    match extension.as_deref() {
+        Some("png") => write_png(path, labels, width, height, is_16bit),
+        Some("tif") | Some("tiff") => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            write_tiff(&mut writer, labels, width, height, is_16bit)?;
+            writer.flush()
+        },
+        Some("npy") => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            write_npy(&mut writer, labels, width, height)?;
+            writer.flush()
+        },
+        _ => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            write_raw(&mut writer, labels)?;
+            writer.flush()
+        },
+    }
+}
+
+fn write_png(path: &Path, labels: &[i32], width: usize, height: usize, is_16bit: bool) -> io::Result<()> {
+    let (width, height) = (width as u32, height as u32);
+    let result = if is_16bit {
+        let levels = labels.iter().map(|&label| (label + 1) as u16).collect();
+        image::ImageBuffer::<image::Luma<u16>, Vec<u16>>::from_raw(width, height, levels)
+            .unwrap()
+            .save_with_format(path, image::ImageFormat::Png)
+    } else {
+        let bytes = labels.iter().flat_map(|&label| ((label + 1) as u32).to_le_bytes().to_vec()).collect();
+        image::RgbaImage::from_raw(width, height, bytes)
+            .unwrap()
+            .save_with_format(path, image::ImageFormat::Png)
+    };
+    result.map_err(io::Error::other)
+}
+
+// baseline TIFF, little-endian with a single uncompressed strip
+fn write_tiff(writer: &mut impl Write, labels: &[i32], width: usize, height: usize, is_16bit: bool) -> io::Result<()> {
+    let bits = if is_16bit { 16 } else { 32 };
+    let strip_len = (labels.len() * bits / 8) as u32;
+    // tag, type (3 for SHORT, 4 for LONG, 5 for RATIONAL) and value, in ascending order of tags.
+    // RATIONAL values do not fit in an entry, they are written after the IFD
+    let entries: [(u16, u16, u32); 13] = [
+        (256, 4, width as u32),   // ImageWidth
+        (257, 4, height as u32),  // ImageLength
+        (258, 3, bits as u32),    // BitsPerSample
+        (259, 3, 1),              // Compression, none
+        (262, 3, 1),              // PhotometricInterpretation, black is zero
+        (273, 4, 0),              // StripOffsets, filled below
+        (277, 3, 1),              // SamplesPerPixel
+        (278, 4, height as u32),  // RowsPerStrip
+        (279, 4, strip_len),      // StripByteCounts
+        (282, 5, 0),              // XResolution, offset filled below
+        (283, 5, 0),              // YResolution, offset filled below
+        (296, 3, 1),              // ResolutionUnit, none
+        (339, 3, 1),              // SampleFormat, unsigned integer
+    ];
+    let ifd_len = 2 + entries.len() as u32 * 12 + 4;
+    let x_resolution_offset = 8 + ifd_len;
+    let y_resolution_offset = x_resolution_offset + 8;
+    let strip_offset = y_resolution_offset + 8;
+    writer.write_all(b"II*\0")?;
+    writer.write_all(&8u32.to_le_bytes())?;
+    writer.write_all(&(entries.len() as u16).to_le_bytes())?;
+    for &(tag, kind, value) in &entries {
+        let value = match tag {
+            273 => strip_offset,
+            282 => x_resolution_offset,
+            283 => y_resolution_offset,
+            _ => value,
+        };
+        writer.write_all(&tag.to_le_bytes())?;
+        writer.write_all(&kind.to_le_bytes())?;
+        writer.write_all(&1u32.to_le_bytes())?;
+        // SHORT values are left-justified in the 4 bytes
+        if kind == 3 {
+            writer.write_all(&(value as u16).to_le_bytes())?;
+            writer.write_all(&[0, 0])?;
+        } else {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    // no next IFD
+    writer.write_all(&0u32.to_le_bytes())?;
+    // XResolution and YResolution, 1 pixel per unit
+    for _ in 0..2 {
+        writer.write_all(&1u32.to_le_bytes())?;
+        writer.write_all(&1u32.to_le_bytes())?;
+    }
+    for &label in labels {
+        if is_16bit {
+            writer.write_all(&((label + 1) as u16).to_le_bytes())?;
+        } else {
+            writer.write_all(&((label + 1) as u32).to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+// NumPy format version 1.0 of an int32 array shaped (height, width)
+fn write_npy(writer: &mut impl Write, labels: &[i32], width: usize, height: usize) -> io::Result<()> {
+    let mut header = format!("{{'descr': '<i4', 'fortran_order': False, 'shape': ({}, {}), }}", height, width);
+    // the data starts at a multiple of 64 bytes, after the magic, version and header length
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    write_raw(writer, labels)
+}
+
+fn write_raw(writer: &mut impl Write, labels: &[i32]) -> io::Result<()> {
+    for &label in labels {
+        writer.write_all(&label.to_le_bytes())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::options::Verbosity;
+
+static VERBOSITY: AtomicUsize = AtomicUsize::new(Verbosity::Normal as usize);
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as usize, Ordering::Relaxed);
+}
+
+pub fn enabled(verbosity: Verbosity) -> bool {
+    verbosity as usize <= VERBOSITY.load(Ordering::Relaxed)
+}
+
+// prints progress unless `--quiet` is given
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::options::Verbosity::Normal) {
+            println!($($arg)*);
+        }
+    };
+}
+
+// prints details only when `--verbose` is given
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::options::Verbosity::Verbose) {
+            println!($($arg)*);
+        }
+    };
+}
@@ -1,140 +1,614 @@
-
 #[macro_use]
 extern crate filum;
 use filum::{Context, BufferViewBuilder, PipelineBuilder, DispatchBuilder};
 
+#[macro_use]
+mod log;
+mod batch;
+mod classes;
+mod filter;
+mod holes;
+mod labels;
+mod options;
+mod outline;
+mod preview;
+mod reference;
+mod rule;
+mod shader;
+mod stats;
+mod tile;
+mod track;
+
+use options::{Options, Algorithm, Foreground};
+use filter::Filter;
+use rule::Rule;
+use tile::Tile;
+use batch::Image;
+
 extern crate image;
 
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::iter::Peekable;
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
 use std::time::Instant;
 
+// the sample image is embedded so that the example runs from any directory
+const SAMPLE_IMAGE: &[u8] = include_bytes!("../res/8k.png");
+
+// number of pixels scanned by one invocation of the compaction shaders
+const COMPACT_BLOCK_SIZE: usize = 1024;
+
+// width and height of the blocks labeled by one workgroup of block.comp,
+// 128 invocations which is the most every device supports
+const BLOCK_WIDTH: usize = 8;
+const BLOCK_HEIGHT: usize = 16;
+
 // This is actually an implementation of the following algorithm.
 // A Parallel Approach to Object Identification in Large-scale Images
 // @see https://www.academia.edu/29842500/
 fn main() {
+    let options = Options::from_args();
+    log::set_verbosity(options.verbosity);
+    let shader_dir = options.shader_dir.as_deref();
+    if options.track.is_some() {
+        let dir = options.input.as_ref().unwrap();
+        let frames = batch::images(dir).unwrap_or_else(|err| {
+            eprintln!("failed to list frames in {}: {}", dir.display(), err);
+            process::exit(1);
+        });
+        if frames.is_empty() {
+            eprintln!("no frames in {}", dir.display());
+            process::exit(1);
+        }
+        if let Some(dir) = &options.track_frames {
+            if let Err(err) = std::fs::create_dir_all(dir) {
+                eprintln!("failed to create {}: {}", dir.display(), err);
+                process::exit(1);
+            }
+        }
+        let context = Context::new().unwrap();
+        track::run(&context, frames, &options, shader_dir);
+        return;
+    }
+    if options.batch {
+        let input = options.input.as_ref().unwrap();
+        let paths = batch::inputs(input).unwrap_or_else(|err| {
+            eprintln!("failed to read {}: {}", input.display(), err);
+            process::exit(1);
+        });
+        if paths.is_empty() {
+            eprintln!("no images in {}", input.display());
+            process::exit(1);
+        }
+        info!("{} images", paths.len());
+        let instant = Instant::now();
+        let context = Context::new().unwrap();
+        let num_failed = Cell::new(0);
+        let mut images = batch::decode(paths, options.classes)
+            .into_iter()
+            .filter_map(|image| {
+                image.map_err(|err| {
+                    eprintln!("{}", err);
+                    num_failed.set(num_failed.get() + 1);
+                }).ok()
+            })
+            .peekable();
+        // the buffers and pipelines are built again only when the dimensions change
+        while images.peek().is_some() {
+            label_images(&context, &mut images, &options, shader_dir, |image, labels, options| {
+                output(labels, &mut image.img, options);
+            });
+        }
+        info!("batch done {:?}", instant.elapsed());
+        if num_failed.get() > 0 {
+            eprintln!("{} images could not be opened", num_failed.get());
+            process::exit(1);
+        }
+        return;
+    }
     // opens image file
-    println!("processing input image");
-    let mut img = image::open("res/8k.png").unwrap();
-    let img_view = img.as_mut_rgba8().unwrap();
-    let (width, height) = img_view.dimensions();
-    let (width, height) = (width as usize, height as usize);
-    let len = width * height;
-    assert!(width.is_power_of_two());
-    println!("shader setup");
+    info!("processing input image");
+    let img = match &options.input {
+        Some(path) => image::open(path),
+        None => image::load_from_memory(SAMPLE_IMAGE),
+    };
+    let img = img.unwrap_or_else(|err| {
+        eprintln!("failed to open input image: {}", err);
+        process::exit(1);
+    });
+    let mut image = Image::new(options.input.clone(), img, options.classes);
+    info!("shader setup");
     // setup some shaders
     let context = Context::new().unwrap();
-    let view = BufferViewBuilder::new(&context)
-        .bind_array::<i32>(len)
+    if options.tile.is_some() {
+        let img_view = &mut image.img;
+        let (width, height) = img_view.dimensions();
+        let (width, height) = (width as usize, height as usize);
+        // labels are the indices of pixels as i32 across the whole image
+        if width * height > i32::MAX as usize {
+            eprintln!("{}x{} is more than {} pixels, the largest image which can be labeled",
+                width, height, i32::MAX);
+            process::exit(1);
+        }
+        let instant = Instant::now();
+        let mut labels = tile::label(&context, img_view, image.classes.as_deref(), &options, shader_dir);
+        // the labels are on the host already
+        if filter::is_enabled(&options) {
+            filter::apply(&mut labels, width, height, &options);
+        }
+        if options.holes || options.fill_holes {
+            let holes = holes::find_on_cpu(&labels, width, height, options.connectivity);
+            info!("{} holes", holes.len());
+            if options.fill_holes {
+                holes.fill(&mut labels);
+            }
+        }
+        if options.compact {
+            info!("{} components", tile::compact(&mut labels));
+        }
+        info!("done {:?}", instant.elapsed());
+        if options.verify {
+            verify(&labels, img_view, image.classes.as_deref(), &options);
+        }
+        output(&labels, img_view, &options);
+        return;
+    }
+    let images = &mut std::iter::once(image).peekable();
+    label_images(&context, images, &options, shader_dir, |image, labels, options| {
+        output(labels, &mut image.img, options);
+    });
+}
+
+// labels the images one after another while they have the dimensions of the first one,
+// with a single buffer view and a single set of pipelines, and passes every image
+// with its labels and its options to `labeled`.
+// returns when the images run out or the next one has other dimensions, which is left in `images`.
+fn label_images(
+    context: &Arc<Context>,
+    images: &mut Peekable<impl Iterator<Item = Image>>,
+    options: &Options,
+    shader_dir: Option<&Path>,
+    mut labeled: impl FnMut(&mut Image, &[i32], &Options),
+) {
+    let dimensions = images.peek().unwrap().img.dimensions();
+    let (width, height) = (dimensions.0 as usize, dimensions.1 as usize);
+    let has_classes = options.classes.is_some();
+    let len = width * height;
+    let num_blocks = len.div_ceil(COMPACT_BLOCK_SIZE);
+    let is_otsu = options.foreground == Foreground::Otsu;
+    let has_holes = options.holes || options.fill_holes;
+    let has_stats = options.stats.is_some();
+    // shared by the luma histogram, the areas of filtering, the offsets of compaction
+    // and the pixels the mean colors are taken from
+    let scratch_len = [
+        1,
+        if is_otsu { 256 } else { 0 },
+        if filter::is_enabled(options) { len } else { 0 },
+        if options.compact { num_blocks + 1 } else { 0 },
+        if has_stats && options.mean_color { len } else { 0 },
+    ].iter().copied().max().unwrap();
+    // the classes, then the statistics table once the components are labeled
+    let num_rows = stats::MAX_ROWS.min(len);
+    let classes_len = [
+        1,
+        if has_classes { len } else { 0 },
+        if has_stats { num_rows * stats::ROW_SIZE } else { 0 },
+    ].iter().copied().max().unwrap();
+    debug!("buffers for {}x{}", width, height);
+    let view = BufferViewBuilder::new(context)
+        .layout(
+            bindings!(
+                binding_array!(i32, len),
+                binding_array!(u32, scratch_len),
+                binding_array!(u32, classes_len),
+            )
+        )
         .build()
         .unwrap();
     let buffer = view.buffer();
+    let foreground = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::FOREGROUND.code(shader_dir))
+        .build()
+        .unwrap();
     let column = PipelineBuilder::new(buffer)
-        .shader("data/column.comp.spv")
-        .specialization(constants!(width as u32, height as u32))
+        .shader_bytes(shader::COLUMN.code(shader_dir))
+        .specialization(constants!(width as u32, height as u32, has_classes as u32))
         .build()
         .unwrap();
     let merge = PipelineBuilder::new(buffer)
-        .shader("data/merge.comp.spv")
-        .specialization(constants!(width as u32, height as u32))
+        .shader_bytes(shader::MERGE.code(shader_dir))
+        .specialization(constants!(width as u32, height as u32, options.connectivity, has_classes as u32))
+        .build()
+        .unwrap();
+    let block = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::BLOCK.code(shader_dir))
+        .specialization(constants!(width as u32, height as u32, options.connectivity, has_classes as u32))
+        .build()
+        .unwrap();
+    let boundary = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::BOUNDARY.code(shader_dir))
+        .specialization(constants!(width as u32, height as u32, options.connectivity, has_classes as u32))
         .build()
         .unwrap();
     let relabel = PipelineBuilder::new(buffer)
-        .shader("data/relabel.comp.spv")
+        .shader_bytes(shader::RELABEL.code(shader_dir))
         .build()
         .unwrap();
-    println!("uploading");
-    let mut instant = Instant::now();
-    let binding = view.binding();
-    binding.update_array(|slice| {
-        input(slice, img_view);
-        instant = Instant::now();
-    });
-    println!("column");
-    column.dispatch(width);
-    println!("merge");
-    let mut step_index = 0;
-    let mut n = width >> 1;
-    while n != 0 {
-        println!("n {}, si {}", n, step_index);
-        let dispatch = DispatchBuilder::new(&merge)
-            .workgroup_count(n, 1, 1)
-            .push_constants(constants!(step_index as u32))
+    let histogram = if is_otsu {
+        let histogram = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::HISTOGRAM.code(shader_dir))
+            .build()
+            .unwrap();
+        Some(histogram)
+    } else {
+        None
+    };
+    let filter = if filter::is_enabled(options) {
+        let area = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::AREA.code(shader_dir))
+            .specialization(constants!(width as u32, height as u32))
+            .build()
+            .unwrap();
+        let filter = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::FILTER.code(shader_dir))
+            .build()
+            .unwrap();
+        Some((area, filter))
+    } else {
+        None
+    };
+    let compact = if options.compact {
+        let count = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::COUNT.code(shader_dir))
+            .specialization(constants!(len as u32, COMPACT_BLOCK_SIZE as u32))
+            .build()
+            .unwrap();
+        let scan = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::SCAN.code(shader_dir))
+            .specialization(constants!(num_blocks as u32))
+            .build()
+            .unwrap();
+        let compact = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::COMPACT.code(shader_dir))
+            .specialization(constants!(len as u32, COMPACT_BLOCK_SIZE as u32))
             .build()
             .unwrap();
-        dispatch.dispatch();
-        n = n >> 1;
+        Some((count, scan, compact))
+    } else {
+        None
+    };
+    // the background is labeled with the dual connectivity and regardless of classes
+    let background = if has_holes {
+        let column = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::COLUMN.code(shader_dir))
+            .specialization(constants!(width as u32, height as u32, 0u32))
+            .build()
+            .unwrap();
+        let merge = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::MERGE.code(shader_dir))
+            .specialization(constants!(width as u32, height as u32, holes::dual(options.connectivity), 0u32))
+            .build()
+            .unwrap();
+        Some((column, merge))
+    } else {
+        None
+    };
+    let measure = if has_stats {
+        let measure = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::STATS.code(shader_dir))
+            .specialization(constants!(width as u32, options.mean_color as u32, num_rows as u32))
+            .build()
+            .unwrap();
+        Some(measure)
+    } else {
+        None
+    };
+    let binding = view.first_binding();
+    let num_blocks_x = width.div_ceil(BLOCK_WIDTH);
+    let num_blocks_y = height.div_ceil(BLOCK_HEIGHT);
+    while let Some(mut image) = images.next_if(|image| image.img.dimensions() == dimensions) {
+        // `{}` in the output paths is replaced by the name of every image of a batch
+        let image_options = image.path.as_ref()
+            .filter(|_| options.batch)
+            .map(|path| options.for_input(path));
+        let options = image_options.as_ref().unwrap_or(options);
+        if let Some(path) = image.path.as_ref().filter(|_| options.batch) {
+            info!("labeling {}", path.display());
+        }
+        let img_view = &mut image.img;
+        let classes = image.classes.as_deref();
+        info!("uploading");
+        let mut instant = Instant::now();
+        binding.update_array(|slice| {
+            input(slice, img_view, &Tile { x: 0, y: 0, width, height });
+            instant = Instant::now();
+        });
+        if let Some(classes) = classes {
+            view.third_binding().update_array_copying(classes);
+        }
+        let otsu_threshold = histogram.as_ref().map(|histogram| {
+            info!("histogram");
+            let binding_histogram = view.second_binding();
+            binding_histogram.update_array(|slice| {
+                slice.iter_mut().for_each(|count| *count = 0);
+            });
+            histogram.dispatch(len);
+            let mut threshold = 0;
+            binding_histogram.fetch_array(|slice| {
+                threshold = rule::otsu(&slice[..256]);
+            });
+            info!("otsu threshold {}", threshold);
+            threshold
+        });
+        let rule = Rule::new(options.foreground, options.invert, otsu_threshold);
+        // labels the uploaded pixels, returns how long the labeling passes took
+        let label = |algorithm: Algorithm| {
+            info!("foreground");
+            let dispatch = DispatchBuilder::new(&foreground)
+                .workgroup_count(len, 1, 1)
+                .push_constants(constants!(rule))
+                .build()
+                .unwrap();
+            dispatch.dispatch();
+            let instant = Instant::now();
+            match algorithm {
+                Algorithm::Strip => {
+                    info!("column");
+                    column.dispatch(width);
+                    info!("merge");
+                    for (n, step_index) in merge_steps(width) {
+                        debug!("n {}, si {}", n, step_index);
+                        let dispatch = DispatchBuilder::new(&merge)
+                            .workgroup_count(n, 1, 1)
+                            .push_constants(constants!(step_index as u32))
+                            .build()
+                            .unwrap();
+                        dispatch.dispatch();
+                    }
+                },
+                Algorithm::Block => {
+                    info!("block");
+                    for pipeline in [&block, &boundary].iter() {
+                        let dispatch = DispatchBuilder::new(pipeline)
+                            .workgroup_count(num_blocks_x, num_blocks_y, 1)
+                            .build()
+                            .unwrap();
+                        dispatch.dispatch();
+                    }
+                },
+            }
+            info!("relabel");
+            relabel.dispatch(len);
+            instant.elapsed()
+        };
+        if options.benchmark {
+            // both algorithms label the components after their first pixels, so the labels must be equal
+            let strip = label(Algorithm::Strip);
+            let mut expected = vec![];
+            binding.fetch_array(|slice| expected = slice.to_vec());
+            binding.update_array(|slice| input(slice, img_view, &Tile { x: 0, y: 0, width, height }));
+            let block = label(Algorithm::Block);
+            let mut mismatch = None;
+            binding.fetch_array(|slice| {
+                mismatch = slice.iter().zip(&expected).position(|(label, expected)| label != expected);
+            });
+            info!("strip {:?}, block {:?}, speed-up {:.2}x", strip, block, strip.as_secs_f64() / block.as_secs_f64());
+            if let Some(index) = mismatch {
+                eprintln!("block labels differ from strip labels at pixel ({}, {})", index % width, index / width);
+                process::exit(1);
+            }
+        } else {
+            label(options.algorithm);
+        }
+        // drops small, border-touching or all but the largest components
+        if let Some((area, filter)) = &filter {
+            info!("filter");
+            let binding_areas = view.second_binding();
+            binding_areas.update_array(|slice| {
+                slice.iter_mut().for_each(|area| *area = 0);
+            });
+            area.dispatch(len);
+            let parameters = if options.keep_largest.is_some() {
+                let mut parameters = None;
+                binding_areas.fetch_array(|slice| {
+                    parameters = Some(Filter::new(options, Some(&slice[..len])));
+                });
+                parameters.unwrap()
+            } else {
+                Filter::new(options, None)
+            };
+            let dispatch = DispatchBuilder::new(filter)
+                .workgroup_count(len, 1, 1)
+                .push_constants(constants!(parameters))
+                .build()
+                .unwrap();
+            dispatch.dispatch();
+        }
+        // renumbers the components to 0..N-1 in the scan order of their roots
+        let mut num_components = None;
+        if let Some((count, scan, compact)) = &compact {
+            info!("compact");
+            count.dispatch(num_blocks);
+            scan.dispatch(1);
+            // numbers roots per block, then resolves the others and decodes roots per pixel
+            for phase in 0..3 {
+                let n = if phase == 0 { num_blocks } else { len };
+                let dispatch = DispatchBuilder::new(compact)
+                    .workgroup_count(n, 1, 1)
+                    .push_constants(constants!(phase as u32))
+                    .build()
+                    .unwrap();
+                dispatch.dispatch();
+            }
+            view.second_binding().fetch_array(|slice| {
+                num_components = Some(slice[num_blocks] as usize);
+            });
+            info!("{} components", num_components.unwrap());
+        }
+        info!("fetching");
+        let mut labels = vec![0; len];
+        binding.fetch_array_copying(&mut labels);
+        info!("done {:?}", instant.elapsed());
+        // the labels are on the host, so the buffers are free for the background and the statistics
+        let holes = background.as_ref().map(|(column, merge)| {
+            info!("labeling background");
+            binding.update_array(|slice| holes::background(slice, &labels));
+            column.dispatch(width);
+            for (n, step_index) in merge_steps(width) {
+                let dispatch = DispatchBuilder::new(merge)
+                    .workgroup_count(n, 1, 1)
+                    .push_constants(constants!(step_index as u32))
+                    .build()
+                    .unwrap();
+                dispatch.dispatch();
+            }
+            relabel.dispatch(len);
+            let mut background = vec![0; len];
+            binding.fetch_array_copying(&mut background);
+            let holes = holes::find(&labels, background, width, height);
+            info!("{} holes", holes.len());
+            holes
+        });
+        if options.fill_holes {
+            holes.as_ref().unwrap().fill(&mut labels);
+        }
+        if let (Some(measure), Some(path)) = (&measure, &options.stats) {
+            info!("measuring components");
+            let (rows, row_labels) = stats::rows(&labels, num_components);
+            binding.update_array_copying(&rows);
+            if options.mean_color {
+                view.second_binding().update_array(|slice| stats::pixels(slice, img_view));
+            }
+            // the components are measured `num_rows` at a time
+            let binding_table = view.third_binding();
+            let mut components = Vec::with_capacity(row_labels.len());
+            for first_row in (0..row_labels.len()).step_by(num_rows) {
+                let pass = &row_labels[first_row..row_labels.len().min(first_row + num_rows)];
+                binding_table.update_array(|slice| stats::clear(slice, pass));
+                let dispatch = DispatchBuilder::new(measure)
+                    .workgroup_count(len, 1, 1)
+                    .push_constants(constants!(first_row as u32))
+                    .build()
+                    .unwrap();
+                dispatch.dispatch();
+                binding_table.fetch_array(|slice| stats::read(slice, pass.len(), options.mean_color, &mut components));
+            }
+            if let Some(classes) = classes {
+                stats::classify(&mut components, &rows, classes);
+            }
+            if let Some(holes) = &holes {
+                for component in components.iter_mut() {
+                    component.holes = Some(holes.counts.get(&(component.label as i32)).copied().unwrap_or(0));
+                }
+            }
+            if let Err(err) = stats::save(path, &components) {
+                eprintln!("failed to save {}: {}", path.display(), err);
+                process::exit(1);
+            }
+            info!("{} components saved as {}", components.len(), path.display());
+        }
+        if options.verify {
+            verify(&labels, img_view, classes, options);
+        }
+        labeled(&mut image, &labels, options);
+    }
+}
+
+// every step merges pairs of adjacent strips of `1 << step_index` columns.
+// the last strip is left for the next step when it has no pair.
+// returns the number of pairs and the step index of every step.
+fn merge_steps(width: usize) -> Vec<(usize, usize)> {
+    let mut steps = vec![];
+    let mut step_index = 0;
+    while (1 << step_index) < width {
+        let stride = 2 << step_index;
+        steps.push(((width - (1 << step_index)).div_ceil(stride), step_index));
         step_index += 1;
     }
-    println!("relabel");
-    relabel.dispatch(len);
-    println!("fetching");
-    binding.fetch_array(|slice| {
-        println!("done {:?}", instant.elapsed());
-        output(slice, img_view);
-    });
+    steps
 }
 
-fn input(slice: &mut [i32], img_view: &mut image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>) {
-    let width = img_view.width();
-    for (elem, pixel) in slice.iter_mut().zip(img_view.enumerate_pixels_mut()) {
-        let (x, y, pixel) = pixel;
-        let index = x + y * width;
-        let image::Rgba(data) = *pixel;
-        let alpha = data[3];
-        if alpha != 0 {
-            *elem = index as i32;
-        } else {
-            *elem = -1;
+// uploads packed RGBA pixels of the tile which foreground.comp turns into initial labels
+fn input(slice: &mut [i32], img_view: &image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>, tile: &Tile) {
+    for y in 0..tile.height {
+        let row = &mut slice[y * tile.width..(y + 1) * tile.width];
+        for (x, elem) in row.iter_mut().enumerate() {
+            let image::Rgba(data) = *img_view.get_pixel((tile.x + x) as u32, (tile.y + y) as u32);
+            *elem = i32::from_le_bytes(data);
         }
     }
 }
 
-fn output(slice: &[i32], img_view: &mut image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>) {
-    let palette = vec![
-        0x580000,
-        0xff4040,
-        0xfd6a6a,
-        0x001d72,
-        0x0645ff,
-        0x5681ff,
-        0x004d02,
-        0x09d20f,
-        0x76ff7a,
-        0x816102,
-        0xffbf00,
-        0xffd558,
-        0x813f00,
-        0xff8615,
-        0xffbf83,
-        0xffd7b2,
-        0x130f0b,
-        0x420056,
-        0x8a00b4,
-        0xde74ff,
-        0xbababa,
-        0x7a7a7a,
-        0x4b4b4b,
-    ]
-        .iter()
-        .map(|v| image::Rgba([(v >> 16) as u8, (v >> 8) as u8, (v >> 0) as u8,255]))
-        .collect::<Vec<_>>();
-    println!("processing output image");
+// compares the labels with the ones of the CPU reference, exits on the first mismatch
+fn verify(labels: &[i32], img_view: &image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>, classes: Option<&[u32]>, options: &Options) {
+    info!("verifying");
+    let (width, height) = img_view.dimensions();
+    let (width, height) = (width as usize, height as usize);
+    let foreground = reference::foreground(img_view, options);
+    let mut expected = reference::label(&foreground, width, height, options.connectivity, classes);
+    if filter::is_enabled(options) {
+        filter::apply(&mut expected, width, height, options);
+    }
+    if options.fill_holes {
+        holes::find_on_cpu(&expected, width, height, options.connectivity).fill(&mut expected);
+    }
+    match reference::compare(labels, &expected, width) {
+        Ok(num_components) => info!("verified {} components", num_components),
+        Err(mismatch) => {
+            eprintln!("verification failed: {}", mismatch);
+            process::exit(1);
+        },
+    }
+}
+
+fn output(slice: &[i32], img_view: &mut image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>, options: &Options) {
     let width = img_view.width() as usize;
-    let mut color_index = 0;
-    let mut colors: HashMap<i32, image::Rgba<u8>> = HashMap::new();
-    for (x, y, pixel) in img_view.enumerate_pixels_mut() {
-        let index = (x + y * width as u32) as usize;
-        let label = slice[index];
-        if label >= 0 {
-            if let Some(color) = colors.get(&label) {
-                *pixel = *color;
-            } else {
-                let color = palette[color_index % palette.len()];
-                colors.insert(label, color);
-                color_index += 1;
-            }
+    if let Some(path) = &options.outlines {
+        info!("tracing outlines");
+        let height = img_view.height() as usize;
+        let mut outlines = outline::trace(slice, width, height, options.connectivity);
+        if let Some(epsilon) = options.simplify {
+            outline::simplify(&mut outlines, epsilon);
+        }
+        if let Err(err) = outline::save(path, &outlines, width, height) {
+            eprintln!("failed to save {}: {}", path.display(), err);
+            process::exit(1);
         }
+        info!("{} outlines saved as {}", outlines.len(), path.display());
+    }
+    if let Some(path) = &options.labels {
+        let height = img_view.height() as usize;
+        if let Err(err) = labels::save(path, slice, width, height) {
+            eprintln!("failed to save {}: {}", path.display(), err);
+            process::exit(1);
+        }
+        info!("labels saved as {}", path.display());
+    }
+    if let Some(path) = &options.mask {
+        let mask = image::GrayImage::from_fn(img_view.width(), img_view.height(), |x, y| {
+            image::Luma([if slice[(x + y * width as u32) as usize] >= 0 { 255 } else { 0 }])
+        });
+        if let Err(err) = mask.save(path) {
+            eprintln!("failed to save {}: {}", path.display(), err);
+            process::exit(1);
+        }
+        info!("mask saved as {}", path.display());
+    }
+    if options.no_preview {
+        return;
+    }
+    info!("processing output image");
+    preview::paint(slice, img_view, options.distinct_neighbors);
+    let path = &options.output;
+    let result = match options.format {
+        Some(format) => img_view.save_with_format(path, format),
+        None => img_view.save(path),
+    };
+    if let Err(err) = result {
+        eprintln!("failed to save {}: {}", path.display(), err);
+        process::exit(1);
     }
-    img_view.save("output.png").unwrap();
-    println!("image saved as output.png");
+    info!("image saved as {}", path.display());
 }
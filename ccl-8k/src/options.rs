@@ -0,0 +1,387 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use image::ImageFormat;
+
+const USAGE: &str = "\
+usage: filum-example-ccl-8k [OPTIONS] [INPUT]
+
+Labels the connected components of INPUT, the embedded 8K sample by default,
+and saves them painted in colors derived from their labels.
+With --batch, `{}` in the output paths is replaced by the file name of every image
+without its extension, e.g. -o out/{}.png --labels out/{}.npy
+
+options:
+    -o, --output <PATH>         output image path [default: output.png]
+    -f, --format <FORMAT>       output image format: png, bmp, tiff, tga
+                                [default: guessed from the output path]
+        --no-preview            does not save the output image
+        --distinct-neighbors    colors components closer than 2 pixels with distinct hues
+                                in the output image
+        --labels <PATH>         saves the labels losslessly, the format is chosen by extension
+                                  png        label + 1 as 16-bit gray, 0 for the background,
+                                             or when a label does not fit, 8-bit RGBA whose
+                                             channels are the bytes of label + 1 in
+                                             little-endian order, requires --compact
+                                  tif        label + 1 as 16-bit gray, 32-bit when a label
+                                             does not fit, 0 for the background
+                                  npy        NumPy int32 array, -1 for the background
+                                  others     raw little-endian i32, -1 for the background
+    -c, --connectivity <N>      pixel connectivity: 4 or 8 [default: 4]
+    -a, --algorithm <NAME>      labeling passes [default: block]
+                                  block   labels blocks of 8x16 pixels in shared memory,
+                                          then joins them along their edges
+                                  strip   scans every column, then merges strips of columns
+                                          in log2(width) steps
+        --benchmark             labels with both algorithms, checks that they agree
+                                and prints the speed-up of block over strip
+        --foreground <RULE>     pixels to be labeled [default: alpha]
+                                  alpha         alpha channel is not zero
+                                  nonblack      any color channel is not zero
+                                  luma:T        luma is T (0-255) or more
+                                  range:R,G,B   every channel is within its range
+                                                MIN-MAX, e.g. range:200-255,0-80,0-80
+                                  otsu          luma is at or above the threshold found
+                                                by Otsu's method
+        --classes <MODE>        labels each class of a segmentation map separately,
+                                pixels connect only when they are in the same class
+                                  palette   every distinct color is a class
+                                  mask      the gray level is the class ID
+        --invert                labels the pixels which do not match the foreground rule
+        --min-area <N>          drops the components smaller than N pixels
+        --keep-largest <N>      keeps the N largest components only, the ones whose first
+                                pixel comes first among those of the same area
+        --remove-border         drops the components which touch the image border
+        --holes                 counts the holes of every component, the background regions
+                                which do not touch the image border and are enclosed by that
+                                component alone, and adds the number of holes and the Euler
+                                number to the statistics
+        --fill-holes            labels the holes with the component around them
+        --mask <PATH>           saves the labeled pixels as a black and white image
+        --outlines <PATH>       saves the outline of every component as polygons with holes,
+                                as SVG when PATH ends with .svg, GeoJSON otherwise
+        --simplify <EPSILON>    simplifies the outlines with the Douglas-Peucker algorithm,
+                                points are kept within EPSILON pixels
+        --compact               renumbers the components to 0..N-1 instead of
+                                labeling them with the index of their first pixel
+        --batch                 labels every image of the directory INPUT, or every path listed
+                                in the file INPUT, with buffers and pipelines reused while the
+                                dimensions stay the same
+        --tile <N>              labels the image in tiles of N x N pixels with GPU buffers of
+                                a single tile, then stitches the tiles together on the host,
+                                for images larger than a storage buffer. the image and
+                                the labels are still held whole in host memory, images of
+                                up to 2^31 - 1 pixels
+        --stats <PATH>          saves the area, bounding box and centroid of every component
+                                as JSON when PATH ends with .json, CSV otherwise
+        --mean-color            adds the mean color of every component to the statistics
+        --verify                checks the labels against a CPU union-find and reports
+                                the first pixel which disagrees
+        --track <PATH>          labels every image of the directory INPUT as a frame of a video,
+                                in the order of file names, follows the components from frame
+                                to frame by their overlap and saves their trajectories as CSV
+        --track-frames <DIR>    saves every frame to DIR with components colored by track
+        --shader-dir <DIR>      loads <DIR>/*.comp.spv instead of the embedded shaders
+    -q, --quiet                 prints errors only
+    -v, --verbose               prints every merge step
+    -h, --help                  prints this message
+";
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Algorithm {
+    Block,
+    Strip,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Foreground {
+    Alpha,
+    NonBlack,
+    Luma(u8),
+    Range([u8; 3], [u8; 3]),
+    Otsu,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Classes {
+    Palette,
+    Mask,
+}
+
+#[derive(Clone)]
+pub struct Options {
+    pub input: Option<PathBuf>,
+    pub output: PathBuf,
+    pub format: Option<ImageFormat>,
+    pub no_preview: bool,
+    pub distinct_neighbors: bool,
+    pub labels: Option<PathBuf>,
+    pub connectivity: u32,
+    pub algorithm: Algorithm,
+    pub benchmark: bool,
+    pub foreground: Foreground,
+    pub invert: bool,
+    pub classes: Option<Classes>,
+    pub min_area: u32,
+    pub keep_largest: Option<usize>,
+    pub remove_border: bool,
+    pub holes: bool,
+    pub fill_holes: bool,
+    pub mask: Option<PathBuf>,
+    pub outlines: Option<PathBuf>,
+    pub simplify: Option<f64>,
+    pub compact: bool,
+    pub batch: bool,
+    pub tile: Option<usize>,
+    pub stats: Option<PathBuf>,
+    pub mean_color: bool,
+    pub verify: bool,
+    pub track: Option<PathBuf>,
+    pub track_frames: Option<PathBuf>,
+    pub shader_dir: Option<PathBuf>,
+    pub verbosity: Verbosity,
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        let mut options = Options {
+            input: None,
+            output: PathBuf::from("output.png"),
+            format: None,
+            no_preview: false,
+            distinct_neighbors: false,
+            labels: None,
+            connectivity: 4,
+            algorithm: Algorithm::Block,
+            benchmark: false,
+            foreground: Foreground::Alpha,
+            invert: false,
+            classes: None,
+            min_area: 0,
+            keep_largest: None,
+            remove_border: false,
+            holes: false,
+            fill_holes: false,
+            mask: None,
+            outlines: None,
+            simplify: None,
+            compact: false,
+            batch: false,
+            tile: None,
+            stats: None,
+            mean_color: false,
+            verify: false,
+            track: None,
+            track_frames: None,
+            shader_dir: None,
+            verbosity: Verbosity::Normal,
+        };
+        // whether -o was given, --track saves no output image
+        let mut has_output = false;
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    options.output = PathBuf::from(value(&mut args, &arg));
+                    has_output = true;
+                },
+                "-f" | "--format" => options.format = Some(parse_format(&value(&mut args, &arg))),
+                "--no-preview" => options.no_preview = true,
+                "--distinct-neighbors" => options.distinct_neighbors = true,
+                "--labels" => options.labels = Some(PathBuf::from(value(&mut args, &arg))),
+                "-c" | "--connectivity" => options.connectivity = match value(&mut args, &arg).as_str() {
+                    "4" => 4,
+                    "8" => 8,
+                    other => fail(&format!("invalid connectivity {}", other)),
+                },
+                "-a" | "--algorithm" => options.algorithm = match value(&mut args, &arg).as_str() {
+                    "block" => Algorithm::Block,
+                    "strip" => Algorithm::Strip,
+                    other => fail(&format!("unknown algorithm {}", other)),
+                },
+                "--benchmark" => options.benchmark = true,
+                "--foreground" => options.foreground = parse_foreground(&value(&mut args, &arg)),
+                "--invert" => options.invert = true,
+                "--classes" => options.classes = match value(&mut args, &arg).as_str() {
+                    "palette" => Some(Classes::Palette),
+                    "mask" => Some(Classes::Mask),
+                    other => fail(&format!("unknown class mode {}", other)),
+                },
+                "--min-area" => options.min_area = value(&mut args, &arg).parse()
+                    .unwrap_or_else(|_| fail("minimum area must be a non-negative integer")),
+                "--keep-largest" => options.keep_largest = match value(&mut args, &arg).parse::<usize>() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => fail("number of components to keep must be a positive integer"),
+                },
+                "--remove-border" => options.remove_border = true,
+                "--holes" => options.holes = true,
+                "--fill-holes" => options.fill_holes = true,
+                "--mask" => options.mask = Some(PathBuf::from(value(&mut args, &arg))),
+                "--outlines" => options.outlines = Some(PathBuf::from(value(&mut args, &arg))),
+                "--simplify" => options.simplify = match value(&mut args, &arg).parse::<f64>() {
+                    Ok(epsilon) if epsilon >= 0.0 => Some(epsilon),
+                    _ => fail("simplification tolerance must be a non-negative number"),
+                },
+                "--compact" => options.compact = true,
+                "--batch" => options.batch = true,
+                "--tile" => options.tile = match value(&mut args, &arg).parse::<usize>() {
+                    Ok(size) if size > 0 => Some(size),
+                    _ => fail("tile size must be a positive integer"),
+                },
+                "--stats" => options.stats = Some(PathBuf::from(value(&mut args, &arg))),
+                "--mean-color" => options.mean_color = true,
+                "--verify" => options.verify = true,
+                "--track" => options.track = Some(PathBuf::from(value(&mut args, &arg))),
+                "--track-frames" => options.track_frames = Some(PathBuf::from(value(&mut args, &arg))),
+                "--shader-dir" => options.shader_dir = Some(PathBuf::from(value(&mut args, &arg))),
+                "-q" | "--quiet" => options.verbosity = Verbosity::Quiet,
+                "-v" | "--verbose" => options.verbosity = Verbosity::Verbose,
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ if arg.starts_with('-') => fail(&format!("unknown option {}", arg)),
+                _ if options.input.is_none() => options.input = Some(PathBuf::from(arg)),
+                _ => fail("only one input can be given"),
+            }
+        }
+        if options.tile.is_some() && options.stats.is_some() {
+            fail("--stats cannot be combined with --tile");
+        }
+        if options.mean_color && options.stats.is_none() {
+            fail("--mean-color requires --stats");
+        }
+        // labels are pixel indices without --compact, which hardly ever fit 16 bits
+        let is_png = options.labels.as_ref()
+            .and_then(|path| path.extension())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        if is_png && !options.compact {
+            fail("--labels as PNG requires --compact, save them as .tif or .npy otherwise");
+        }
+        if options.tile.is_some() && options.benchmark {
+            fail("--benchmark cannot be combined with --tile");
+        }
+        if options.batch {
+            if options.input.is_none() {
+                fail("--batch requires a directory or a list of images as INPUT");
+            }
+            if options.tile.is_some() {
+                fail("--tile cannot be combined with --batch");
+            }
+            if options.track.is_some() {
+                fail("--track cannot be combined with --batch");
+            }
+            // every image would overwrite the outputs of the previous one
+            let outputs = [
+                (Some(&options.output).filter(|_| !options.no_preview), "--output"),
+                (options.labels.as_ref(), "--labels"),
+                (options.mask.as_ref(), "--mask"),
+                (options.outlines.as_ref(), "--outlines"),
+                (options.stats.as_ref(), "--stats"),
+            ];
+            for &(path, name) in &outputs {
+                if path.is_some_and(|path| !path.to_string_lossy().contains("{}")) {
+                    fail(&format!("{} must contain {{}} with --batch", name));
+                }
+            }
+        }
+        if options.track_frames.is_some() && options.track.is_none() {
+            fail("--track-frames requires --track");
+        }
+        if options.track.is_some() {
+            if options.input.is_none() {
+                fail("--track requires a directory of frames as INPUT");
+            }
+            let single_image = [
+                (has_output, "--output"),
+                (options.format.is_some(), "--format"),
+                (options.tile.is_some(), "--tile"),
+                (options.benchmark, "--benchmark"),
+                (options.compact, "--compact"),
+                (options.stats.is_some(), "--stats"),
+                (options.holes, "--holes"),
+                (options.fill_holes, "--fill-holes"),
+                (options.mask.is_some(), "--mask"),
+                (options.outlines.is_some(), "--outlines"),
+                (options.labels.is_some(), "--labels"),
+            ];
+            for &(is_given, name) in &single_image {
+                if is_given {
+                    fail(&format!("{} cannot be combined with --track", name));
+                }
+            }
+        }
+        options
+    }
+}
+
+impl Options {
+    // the options of an image of a batch, with `{}` in the output paths replaced by
+    // the file name of the image without its extension
+    pub fn for_input(&self, input: &Path) -> Options {
+        let name = input.file_stem().unwrap_or_default().to_string_lossy();
+        let replace = |path: &PathBuf| PathBuf::from(path.to_string_lossy().replace("{}", &name));
+        Options {
+            output: replace(&self.output),
+            labels: self.labels.as_ref().map(replace),
+            mask: self.mask.as_ref().map(replace),
+            outlines: self.outlines.as_ref().map(replace),
+            stats: self.stats.as_ref().map(replace),
+            ..self.clone()
+        }
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, name: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| fail(&format!("{} requires a value", name)))
+}
+
+fn parse_foreground(rule: &str) -> Foreground {
+    let invalid = || -> ! { fail(&format!("invalid foreground rule {}", rule)) };
+    let channel = |value: &str| value.parse::<u8>().unwrap_or_else(|_| invalid());
+    match rule.split_once(':') {
+        None if rule == "alpha" => Foreground::Alpha,
+        None if rule == "nonblack" => Foreground::NonBlack,
+        None if rule == "otsu" => Foreground::Otsu,
+        Some(("luma", threshold)) => Foreground::Luma(channel(threshold)),
+        Some(("range", ranges)) => {
+            let ranges: Vec<&str> = ranges.split(',').collect();
+            if ranges.len() != 3 {
+                invalid();
+            }
+            let mut min = [0; 3];
+            let mut max = [0; 3];
+            for (index, range) in ranges.iter().enumerate() {
+                let (lower, upper) = range.split_once('-').unwrap_or_else(|| invalid());
+                min[index] = channel(lower);
+                max[index] = channel(upper);
+            }
+            Foreground::Range(min, max)
+        },
+        _ => invalid(),
+    }
+}
+
+fn parse_format(name: &str) -> ImageFormat {
+    match name {
+        "png" => ImageFormat::Png,
+        "bmp" => ImageFormat::Bmp,
+        "tif" | "tiff" => ImageFormat::Tiff,
+        "tga" => ImageFormat::Tga,
+        other => fail(&format!("unsupported output format {}", other)),
+    }
+}
+
+pub fn fail(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+type Point = (i64, i64);
+
+// a ring is closed, its last point connects back to the first one
+pub struct Polygon {
+    pub exterior: Vec<Point>,
+    pub holes: Vec<Vec<Point>>,
+}
+
+pub struct Outline {
+    pub label: i32,
+    pub polygons: Vec<Polygon>,
+}
+
+// an edge between a pixel of `label` and a pixel of another label or the image border.
+// edges go clockwise around the pixels of the label as seen on the image,
+// so that exteriors are counterclockwise and holes clockwise when y points up.
+struct Edge {
+    label: i32,
+    from: Point,
+    to: Point,
+}
+
+// traces the outlines of every component along the pixel edges, sorted by label.
+// with 8-connectivity the outline passes through the corners where pixels touch diagonally,
+// with 4-connectivity it turns away from them.
+pub fn trace(labels: &[i32], width: usize, height: usize, connectivity: u32) -> Vec<Outline> {
+    let label_at = |x: i64, y: i64| {
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            -1
+        } else {
+            labels[y as usize * width + x as usize]
+        }
+    };
+    let mut edges = vec![];
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let label = label_at(x, y);
+            if label < 0 {
+                continue;
+            }
+            if label_at(x, y - 1) != label {
+                edges.push(Edge { label, from: (x, y), to: (x + 1, y) });
+            }
+            if label_at(x + 1, y) != label {
+                edges.push(Edge { label, from: (x + 1, y), to: (x + 1, y + 1) });
+            }
+            if label_at(x, y + 1) != label {
+                edges.push(Edge { label, from: (x + 1, y + 1), to: (x, y + 1) });
+            }
+            if label_at(x - 1, y) != label {
+                edges.push(Edge { label, from: (x, y + 1), to: (x, y) });
+            }
+        }
+    }
+    let mut outgoing: HashMap<(i32, Point), Vec<usize>> = HashMap::new();
+    for (index, edge) in edges.iter().enumerate() {
+        outgoing.entry((edge.label, edge.from)).or_default().push(index);
+    }
+    // rings of every label
+    let mut rings: BTreeMap<i32, Vec<Vec<Point>>> = BTreeMap::new();
+    let mut used = vec![false; edges.len()];
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        let label = edges[start].label;
+        let mut ring = vec![];
+        let mut current = start;
+        loop {
+            used[current] = true;
+            let edge = &edges[current];
+            ring.push(edge.from);
+            let direction = (edge.to.0 - edge.from.0, edge.to.1 - edge.from.1);
+            // two edges leave the corners where pixels of the label touch diagonally
+            let next = outgoing[&(label, edge.to)].iter()
+                .copied()
+                .filter(|&next| !used[next] || next == start)
+                .min_by_key(|&next| {
+                    let next = &edges[next];
+                    let turn = (next.to.0 - next.from.0, next.to.1 - next.from.1);
+                    let is_left = turn == (direction.1, -direction.0);
+                    is_left != (connectivity == 8)
+                })
+                .unwrap();
+            if next == start {
+                break;
+            }
+            current = next;
+        }
+        rings.entry(label).or_default().push(remove_collinear(ring));
+    }
+    rings.into_iter()
+        .map(|(label, rings)| Outline { label, polygons: assemble(rings) })
+        .collect()
+}
+
+// the corners of the pixel edges, without the points along straight lines
+fn remove_collinear(ring: Vec<Point>) -> Vec<Point> {
+    let len = ring.len();
+    (0..len)
+        .filter(|&index| {
+            let previous = ring[(index + len - 1) % len];
+            let point = ring[index];
+            let next = ring[(index + 1) % len];
+            (point.0 - previous.0) * (next.1 - point.1) != (point.1 - previous.1) * (next.0 - point.0)
+        })
+        .map(|index| ring[index])
+        .collect()
+}
+
+// twice the area, positive for exteriors and negative for holes
+fn signed_area(ring: &[Point]) -> i64 {
+    let len = ring.len();
+    (0..len)
+        .map(|index| {
+            let (x0, y0) = ring[index];
+            let (x1, y1) = ring[(index + 1) % len];
+            x0 * y1 - x1 * y0
+        })
+        .sum()
+}
+
+// even-odd rule, `point` is given in half pixels so that it never lies on an edge
+fn contains(ring: &[Point], point: (i64, i64)) -> bool {
+    let len = ring.len();
+    let mut inside = false;
+    for index in 0..len {
+        let (x0, y0) = (ring[index].0 * 2, ring[index].1 * 2);
+        let (x1, y1) = (ring[(index + 1) % len].0 * 2, ring[(index + 1) % len].1 * 2);
+        if (y0 > point.1) != (y1 > point.1)
+            && (point.0 - x0) * (y1 - y0) * (y1 - y0).signum() < (x1 - x0) * (point.1 - y0) * (y1 - y0).signum() {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// puts every hole into the smallest exterior around it
+fn assemble(rings: Vec<Vec<Point>>) -> Vec<Polygon> {
+    let (exteriors, holes): (Vec<_>, Vec<_>) = rings.into_iter()
+        .partition(|ring| signed_area(ring) > 0);
+    let mut polygons: Vec<Polygon> = exteriors.into_iter()
+        .map(|exterior| Polygon { exterior, holes: vec![] })
+        .collect();
+    for hole in holes {
+        // the center of the pixel on the left of the first edge is inside the hole
+        let (x0, y0) = hole[0];
+        let (x1, y1) = hole[1];
+        let (dx, dy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let point = (x0 * 2 + dx + dy, y0 * 2 + dy - dx);
+        let index = (0..polygons.len())
+            .filter(|&index| contains(&polygons[index].exterior, point))
+            .min_by_key(|&index| signed_area(&polygons[index].exterior))
+            .unwrap_or(0);
+        polygons[index].holes.push(hole);
+    }
+    polygons
+}
+
+// Douglas-Peucker simplification of every ring, rings are kept as triangles at least
+pub fn simplify(outlines: &mut [Outline], epsilon: f64) {
+    for outline in outlines.iter_mut() {
+        for polygon in outline.polygons.iter_mut() {
+            simplify_ring(&mut polygon.exterior, epsilon);
+            for hole in polygon.holes.iter_mut() {
+                simplify_ring(hole, epsilon);
+            }
+        }
+    }
+}
+
+fn simplify_ring(ring: &mut Vec<Point>, epsilon: f64) {
+    if ring.len() <= 3 {
+        return;
+    }
+    // splits the ring at the point farthest from the first one
+    let distance = |a: Point, b: Point| (((a.0 - b.0).pow(2) + (a.1 - b.1).pow(2)) as f64).sqrt();
+    let farthest = (1..ring.len())
+        .max_by(|&i, &j| distance(ring[0], ring[i]).partial_cmp(&distance(ring[0], ring[j])).unwrap())
+        .unwrap();
+    let mut keep = vec![false; ring.len()];
+    keep[0] = true;
+    keep[farthest] = true;
+    let mut closed = ring.clone();
+    closed.push(ring[0]);
+    douglas_peucker(&closed, 0, farthest, epsilon, &mut keep);
+    douglas_peucker(&closed, farthest, ring.len(), epsilon, &mut keep);
+    if keep.iter().filter(|&&keep| keep).count() >= 3 {
+        let mut index = 0;
+        ring.retain(|_| {
+            index += 1;
+            keep[index - 1]
+        });
+    }
+}
+
+fn douglas_peucker(points: &[Point], first: usize, last: usize, epsilon: f64, keep: &mut [bool]) {
+    let (x0, y0) = (points[first].0 as f64, points[first].1 as f64);
+    let (x1, y1) = (points[last].0 as f64, points[last].1 as f64);
+    let length = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+    let mut max_distance = 0.0;
+    let mut farthest = first;
+    for (index, point) in points.iter().enumerate().take(last).skip(first + 1) {
+        let (x, y) = (point.0 as f64, point.1 as f64);
+        let distance = if length == 0.0 {
+            ((x - x0).powi(2) + (y - y0).powi(2)).sqrt()
+        } else {
+            ((x1 - x0) * (y0 - y) - (x0 - x) * (y1 - y0)).abs() / length
+        };
+        if distance > max_distance {
+            max_distance = distance;
+            farthest = index;
+        }
+    }
+    if max_distance > epsilon {
+        keep[farthest] = true;
+        douglas_peucker(points, first, farthest, epsilon, keep);
+        douglas_peucker(points, farthest, last, epsilon, keep);
+    }
+}
+
+// writes SVG when the path ends with `.svg`, GeoJSON otherwise
+pub fn save(path: &Path, outlines: &[Outline], width: usize, height: usize) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if path.extension() == Some("svg".as_ref()) {
+        write_svg(&mut writer, outlines, width, height)?;
+    } else {
+        write_geojson(&mut writer, outlines)?;
+    }
+    writer.flush()
+}
+
+fn write_geojson(writer: &mut impl Write, outlines: &[Outline]) -> io::Result<()> {
+    let ring = |ring: &[Point]| {
+        // GeoJSON repeats the first position at the end
+        let positions: Vec<String> = ring.iter()
+            .chain(ring.first())
+            .map(|(x, y)| format!("[{}, {}]", x, y))
+            .collect();
+        format!("[{}]", positions.join(", "))
+    };
+    let polygon = |polygon: &Polygon| {
+        let rings: Vec<String> = std::iter::once(&polygon.exterior)
+            .chain(&polygon.holes)
+            .map(|points| ring(points))
+            .collect();
+        format!("[{}]", rings.join(", "))
+    };
+    writeln!(writer, "{{\"type\": \"FeatureCollection\", \"features\": [")?;
+    for (index, outline) in outlines.iter().enumerate() {
+        let polygons: Vec<String> = outline.polygons.iter().map(polygon).collect();
+        let geometry = if polygons.len() == 1 {
+            format!("{{\"type\": \"Polygon\", \"coordinates\": {}}}", polygons[0])
+        } else {
+            format!("{{\"type\": \"MultiPolygon\", \"coordinates\": [{}]}}", polygons.join(", "))
+        };
+        let separator = if index + 1 < outlines.len() { "," } else { "" };
+        writeln!(writer, "  {{\"type\": \"Feature\", \"properties\": {{\"label\": {}}}, \"geometry\": {}}}{}",
+            outline.label, geometry, separator)?;
+    }
+    writeln!(writer, "]}}")
+}
+
+fn write_svg(writer: &mut impl Write, outlines: &[Outline], width: usize, height: usize) -> io::Result<()> {
+    writeln!(writer, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+        width, height)?;
+    writeln!(writer, "<g fill-opacity=\"0.5\" fill-rule=\"evenodd\" stroke=\"#000000\" stroke-width=\"0.5\">")?;
+    for outline in outlines {
+        let mut data = String::new();
+        for polygon in &outline.polygons {
+            for ring in std::iter::once(&polygon.exterior).chain(&polygon.holes) {
+                for (index, (x, y)) in ring.iter().enumerate() {
+                    data.push_str(&format!("{}{} {} ", if index == 0 { "M" } else { "L" }, x, y));
+                }
+                data.push_str("Z ");
+            }
+        }
+        // the same color as the output image without `--distinct-neighbors`
+        let image::Rgba([r, g, b, _]) = crate::preview::color(outline.label);
+        writeln!(writer, "<path id=\"label-{}\" fill=\"#{:02x}{:02x}{:02x}\" d=\"{}\"/>",
+            outline.label, r, g, b, data.trim_end())?;
+    }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")
+}
@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// components closer than this many pixels are neighbors
+const NEIGHBOR_DISTANCE: i64 = 2;
+
+// hues of neighbors are kept at least this many degrees apart when possible
+const MIN_HUE_DISTANCE: f64 = 40.0;
+
+// step in degrees between the hues tried for a component whose hue is too close to a neighbor
+const HUE_STEP: f64 = 1.0;
+
+// paints every pixel of the components in the color of its label, the background is left as is.
+// with `distinct_neighbors`, hues are picked again for components whose hue is close to
+// the one of a neighbor which comes before them.
+pub fn paint(labels: &[i32], img_view: &mut image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>, distinct_neighbors: bool) {
+    let (width, height) = img_view.dimensions();
+    let hues = if distinct_neighbors {
+        pick_hues(&neighbors(labels, width as usize, height as usize))
+    } else {
+        HashMap::new()
+    };
+    for (pixel, &label) in img_view.pixels_mut().zip(labels) {
+        if label >= 0 {
+            *pixel = color_with_hue(label, hues.get(&label).copied());
+        }
+    }
+}
+
+// the color of a label
+pub fn color(label: i32) -> image::Rgba<u8> {
+    color_with_hue(label, None)
+}
+
+// the color of a label, with `hue` in place of its own one when given
+fn color_with_hue(label: i32, hue: Option<f64>) -> image::Rgba<u8> {
+    let (own_hue, saturation, value) = hsv(label);
+    let [r, g, b] = hsv_to_rgb(hue.unwrap_or(own_hue), saturation, value);
+    image::Rgba([r, g, b, 255])
+}
+
+// hue in degrees, bright and saturated enough to stand out from each other
+fn hsv(label: i32) -> (f64, f64, f64) {
+    let hash = splitmix64(label as u32 as u64);
+    let hue = (hash & 0xffff) as f64 / 65536.0 * 360.0;
+    let saturation = 0.6 + ((hash >> 16) & 0xff) as f64 / 255.0 * 0.4;
+    let value = 0.75 + ((hash >> 24) & 0xff) as f64 / 255.0 * 0.25;
+    (hue, saturation, value)
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> [u8; 3] {
+    let chroma = value * saturation;
+    let h = hue / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+    [channel(r), channel(g), channel(b)]
+}
+
+// pairs of labels whose pixels are closer than `NEIGHBOR_DISTANCE`, the smaller label first
+fn neighbors(labels: &[i32], width: usize, height: usize) -> BTreeMap<i32, BTreeSet<i32>> {
+    // offsets which come after the pixel in scan order, the others are found from the other side
+    let mut offsets = vec![];
+    for dy in 0..=NEIGHBOR_DISTANCE {
+        for dx in -NEIGHBOR_DISTANCE..=NEIGHBOR_DISTANCE {
+            if dy > 0 || dx > 0 {
+                offsets.push((dx, dy));
+            }
+        }
+    }
+    let mut neighbors: BTreeMap<i32, BTreeSet<i32>> = BTreeMap::new();
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let label = labels[y as usize * width + x as usize];
+            if label < 0 {
+                continue;
+            }
+            for &(dx, dy) in &offsets {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || nx as usize >= width || ny as usize >= height {
+                    continue;
+                }
+                let other = labels[ny as usize * width + nx as usize];
+                if other >= 0 && other != label {
+                    neighbors.entry(label.max(other)).or_default().insert(label.min(other));
+                }
+            }
+        }
+    }
+    neighbors
+}
+
+// colors the graph of neighbors greedily in the order of labels, so that the result
+// does not depend on anything else. every label keeps its own hue unless it is too close to
+// the hue of a neighbor with a smaller label, then it gets the first hue from its own one on
+// which is far enough from all of them, or the farthest one when there is none.
+// returns the hues of the labels which do not keep their own.
+fn pick_hues(neighbors: &BTreeMap<i32, BTreeSet<i32>>) -> HashMap<i32, f64> {
+    let mut hues: HashMap<i32, f64> = HashMap::new();
+    let hue_distance = |a: f64, b: f64| {
+        let distance = (a - b).abs();
+        distance.min(360.0 - distance)
+    };
+    for (&label, smaller) in neighbors {
+        let neighbor_hues: Vec<f64> = smaller.iter()
+            .map(|other| hues.get(other).copied().unwrap_or_else(|| hsv(*other).0))
+            .collect();
+        let closest = |hue: f64| {
+            neighbor_hues.iter().map(|&other| hue_distance(hue, other)).fold(360.0, f64::min)
+        };
+        let own_hue = hsv(label).0;
+        if closest(own_hue) >= MIN_HUE_DISTANCE {
+            continue;
+        }
+        let candidates = (0..(360.0 / HUE_STEP) as usize)
+            .map(|step| (own_hue + step as f64 * HUE_STEP) % 360.0);
+        let hue = candidates.clone()
+            .find(|&hue| closest(hue) >= MIN_HUE_DISTANCE)
+            .unwrap_or_else(|| {
+                candidates
+                    .max_by(|&a, &b| closest(a).partial_cmp(&closest(b)).unwrap())
+                    .unwrap()
+            });
+        hues.insert(label, hue);
+    }
+    hues
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::options::{Options, Foreground};
+use crate::rule::{self, Rule};
+
+// applies the foreground rule of `options` on the CPU
+pub fn foreground(img_view: &image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>, options: &Options) -> Vec<bool> {
+    let otsu_threshold = if options.foreground == Foreground::Otsu {
+        let mut histogram = vec![0; 256];
+        for image::Rgba(data) in img_view.pixels() {
+            histogram[rule::luma(*data) as usize] += 1;
+        }
+        Some(rule::otsu(&histogram))
+    } else {
+        None
+    };
+    let rule = Rule::new(options.foreground, options.invert, otsu_threshold);
+    img_view.pixels()
+        .map(|image::Rgba(data)| rule.is_foreground(*data))
+        .collect()
+}
+
+// labels `foreground` on the CPU with the classic two-pass union-find.
+// every component is labeled with the index of its first pixel like the shaders do,
+// the background with -1. pixels connect only within the same class when `classes` is given.
+pub fn label(foreground: &[bool], width: usize, height: usize, connectivity: u32, classes: Option<&[u32]>) -> Vec<i32> {
+    let len = width * height;
+    let mut parents: Vec<usize> = (0..len).collect();
+    // the neighbors scanned before the pixel itself
+    let neighbors: &[(isize, isize)] = if connectivity == 8 {
+        &[(-1, 0), (-1, -1), (0, -1), (1, -1)]
+    } else {
+        &[(-1, 0), (0, -1)]
+    };
+    // first pass, joins every pixel with its preceding neighbors
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            if !foreground[index] {
+                continue;
+            }
+            for &(dx, dy) in neighbors {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx < 0 || ny < 0 || nx as usize >= width {
+                    continue;
+                }
+                let neighbor = ny as usize * width + nx as usize;
+                let is_same_class = classes.is_none_or(|classes| classes[index] == classes[neighbor]);
+                if foreground[neighbor] && is_same_class {
+                    let root0 = find(&mut parents, index);
+                    let root1 = find(&mut parents, neighbor);
+                    parents[root0.max(root1)] = root0.min(root1);
+                }
+            }
+        }
+    }
+    // second pass, resolves every pixel to its root
+    (0..len)
+        .map(|index| if foreground[index] { find(&mut parents, index) as i32 } else { -1 })
+        .collect()
+}
+
+fn find(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    // path compression
+    let mut index = index;
+    while parents[index] != root {
+        let parent = parents[index];
+        parents[index] = root;
+        index = parent;
+    }
+    root
+}
+
+// the first pixel whose label disagrees with the reference
+pub struct Mismatch {
+    pub x: usize,
+    pub y: usize,
+    pub kind: MismatchKind,
+}
+
+pub enum MismatchKind {
+    // foreground in one labeling and background in the other
+    Background { label: i32, expected: i32 },
+    // the label covers two components of the reference
+    Merged { label: i32, expected: [i32; 2] },
+    // the component of the reference is covered by two labels
+    Split { labels: [i32; 2], expected: i32 },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pixel ({}, {}) ", self.x, self.y)?;
+        match self.kind {
+            MismatchKind::Background { label, expected } =>
+                write!(f, "is labeled {} where the reference has {}", label, expected),
+            MismatchKind::Merged { label, expected: [expected0, expected1] } =>
+                write!(f, "joins the components {} and {} of the reference as label {}", expected0, expected1, label),
+            MismatchKind::Split { labels: [label0, label1], expected } =>
+                write!(f, "is labeled {} where the rest of component {} of the reference is labeled {}", label1, expected, label0),
+        }
+    }
+}
+
+// checks that `labels` partitions the pixels in the same way as `reference`,
+// regardless of the values of the labels. returns the number of components.
+pub fn compare(labels: &[i32], reference: &[i32], width: usize) -> Result<usize, Mismatch> {
+    let mut forward: HashMap<i32, i32> = HashMap::new();
+    let mut backward: HashMap<i32, i32> = HashMap::new();
+    for (index, (&label, &expected)) in labels.iter().zip(reference).enumerate() {
+        let kind = if label < 0 || expected < 0 {
+            if label < 0 && expected < 0 {
+                continue;
+            }
+            MismatchKind::Background { label, expected }
+        } else {
+            let other = *forward.entry(label).or_insert(expected);
+            if other != expected {
+                MismatchKind::Merged { label, expected: [other, expected] }
+            } else {
+                let other = *backward.entry(expected).or_insert(label);
+                if other != label {
+                    MismatchKind::Split { labels: [other, label], expected }
+                } else {
+                    continue;
+                }
+            }
+        };
+        return Err(Mismatch { x: index % width, y: index / width, kind });
+    }
+    Ok(forward.len())
+}
@@ -0,0 +1,97 @@
+use crate::options::Foreground;
+
+// push constant of foreground.comp
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct Rule {
+    kind: u32,
+    invert: u32,
+    threshold: u32,
+    reserved0: u32,
+    min_color: [u32; 4],
+    max_color: [u32; 4],
+}
+
+impl Rule {
+    // `otsu_threshold` is used in place of `Foreground::Otsu`
+    pub fn new(foreground: Foreground, invert: bool, otsu_threshold: Option<u32>) -> Self {
+        let mut rule = Rule {
+            kind: 0,
+            invert: invert as u32,
+            threshold: 0,
+            reserved0: 0,
+            min_color: [0; 4],
+            max_color: [255; 4],
+        };
+        match foreground {
+            Foreground::Alpha => rule.kind = 0,
+            Foreground::NonBlack => rule.kind = 1,
+            Foreground::Luma(threshold) => {
+                rule.kind = 2;
+                rule.threshold = threshold as u32;
+            },
+            Foreground::Otsu => {
+                rule.kind = 2;
+                rule.threshold = otsu_threshold.unwrap();
+            },
+            Foreground::Range(min, max) => {
+                rule.kind = 3;
+                for channel in 0..3 {
+                    rule.min_color[channel] = min[channel] as u32;
+                    rule.max_color[channel] = max[channel] as u32;
+                }
+            },
+        }
+        rule
+    }
+
+    // same as `isForeground` of foreground.comp
+    pub fn is_foreground(&self, pixel: [u8; 4]) -> bool {
+        let color = [pixel[0] as u32, pixel[1] as u32, pixel[2] as u32];
+        let foreground = match self.kind {
+            0 => pixel[3] != 0,
+            1 => color.iter().any(|&channel| channel != 0),
+            2 => luma(pixel) >= self.threshold,
+            _ => (0..3).all(|channel| {
+                self.min_color[channel] <= color[channel] && color[channel] <= self.max_color[channel]
+            }),
+        };
+        foreground != (self.invert != 0)
+    }
+}
+
+// Rec. 601 luma in 0..255, same as the one of the shaders
+pub fn luma(pixel: [u8; 4]) -> u32 {
+    (299 * pixel[0] as u32 + 587 * pixel[1] as u32 + 114 * pixel[2] as u32) / 1000
+}
+
+// Otsu's method, finds the luma threshold which maximizes the variance
+// between the two classes of the 256-bin histogram.
+// pixels whose luma is at least the threshold are in the upper class.
+pub fn otsu(histogram: &[u32]) -> u32 {
+    let total: f64 = histogram.iter().map(|&count| count as f64).sum();
+    let sum: f64 = histogram.iter()
+        .enumerate()
+        .map(|(luma, &count)| luma as f64 * count as f64)
+        .sum();
+    let mut weight_lower = 0.0;
+    let mut sum_lower = 0.0;
+    let mut max_variance = 0.0;
+    let mut threshold = 0;
+    for (luma, &count) in histogram.iter().enumerate() {
+        weight_lower += count as f64;
+        sum_lower += luma as f64 * count as f64;
+        let weight_upper = total - weight_lower;
+        if weight_lower == 0.0 || weight_upper == 0.0 {
+            continue;
+        }
+        let mean_lower = sum_lower / weight_lower;
+        let mean_upper = (sum - sum_lower) / weight_upper;
+        let variance = weight_lower * weight_upper * (mean_lower - mean_upper).powi(2);
+        if variance > max_variance {
+            max_variance = variance;
+            threshold = luma as u32 + 1;
+        }
+    }
+    threshold
+}
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// number of words per row of the table filled by `stats.comp`
+pub const ROW_SIZE: usize = 16;
+
+// number of components measured per pass of `stats.comp`, which bounds the size of the table
+pub const MAX_ROWS: usize = 1 << 16;
+
+pub struct Component {
+    pub label: u32,
+    pub area: u32,
+    pub min: (u32, u32),
+    pub max: (u32, u32),
+    pub centroid: (f64, f64),
+    pub color: Option<[f64; 3]>,
+    pub class: Option<u32>,
+    pub holes: Option<u32>,
+}
+
+// numbers the components in the order of their labels, which are the rows of the table.
+// `num_components` is given when the labels have been compacted to 0..N-1 and are the rows
+// already, otherwise the components are found by their roots, which come first in scan order.
+// returns the row of every pixel, -1 for the background, and the label of every row.
+pub fn rows(labels: &[i32], num_components: Option<usize>) -> (Vec<i32>, Vec<u32>) {
+    if let Some(num_components) = num_components {
+        return (labels.to_vec(), (0..num_components as u32).collect());
+    }
+    let mut rows = vec![-1; labels.len()];
+    let mut row_labels = vec![];
+    for (index, &label) in labels.iter().enumerate() {
+        if label == index as i32 {
+            rows[index] = row_labels.len() as i32;
+            row_labels.push(label as u32);
+        } else if label >= 0 {
+            rows[index] = rows[label as usize];
+        }
+    }
+    (rows, row_labels)
+}
+
+// packed RGBA pixels the mean colors are taken from
+pub fn pixels(slice: &mut [u32], img_view: &image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>) {
+    for (elem, pixel) in slice.iter_mut().zip(img_view.pixels()) {
+        let image::Rgba(data) = *pixel;
+        *elem = u32::from_le_bytes(data);
+    }
+}
+
+// starts the rows of a pass, `row_labels` holds the labels of the rows measured by the pass
+pub fn clear(table: &mut [u32], row_labels: &[u32]) {
+    for (row, &label) in table.chunks_mut(ROW_SIZE).zip(row_labels) {
+        for (index, value) in row.iter_mut().enumerate() {
+            // MIN_X and MIN_Y start from the largest value
+            *value = if index == 2 || index == 3 { u32::MAX } else { 0 };
+        }
+        row[0] = label;
+    }
+}
+
+// reads the rows of a pass which holds `num_rows` components
+pub fn read(table: &[u32], num_rows: usize, colors: bool, components: &mut Vec<Component>) {
+    for row in table.chunks(ROW_SIZE).take(num_rows) {
+        let sum = |index: usize| (row[index] as u64 | (row[index + 1] as u64) << 32) as f64;
+        let area = row[1];
+        let mean = |index: usize| sum(index) / area as f64;
+        components.push(Component {
+            label: row[0],
+            area,
+            min: (row[2], row[3]),
+            max: (row[4], row[5]),
+            centroid: (mean(6), mean(8)),
+            color: if colors { Some([mean(10), mean(12), mean(14)]) } else { None },
+            class: None,
+            holes: None,
+        });
+    }
+}
+
+// any pixel of a component tells its class
+pub fn classify(components: &mut [Component], rows: &[i32], classes: &[u32]) {
+    for (&row, &class) in rows.iter().zip(classes) {
+        if row >= 0 {
+            components[row as usize].class = Some(class);
+        }
+    }
+}
+
+// writes JSON when the path ends with `.json`, CSV otherwise
+pub fn save(path: &Path, components: &[Component]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if path.extension() == Some("json".as_ref()) {
+        write_json(&mut writer, components)?;
+    } else {
+        write_csv(&mut writer, components)?;
+    }
+    writer.flush()
+}
+
+fn write_csv(writer: &mut impl Write, components: &[Component]) -> io::Result<()> {
+    write!(writer, "label,area,min_x,min_y,max_x,max_y,centroid_x,centroid_y")?;
+    if components.iter().any(|component| component.color.is_some()) {
+        write!(writer, ",mean_r,mean_g,mean_b")?;
+    }
+    if components.iter().any(|component| component.class.is_some()) {
+        write!(writer, ",class")?;
+    }
+    if components.iter().any(|component| component.holes.is_some()) {
+        write!(writer, ",holes,euler")?;
+    }
+    writeln!(writer)?;
+    for component in components {
+        write!(writer, "{},{},{},{},{},{},{:.3},{:.3}",
+            component.label,
+            component.area,
+            component.min.0,
+            component.min.1,
+            component.max.0,
+            component.max.1,
+            component.centroid.0,
+            component.centroid.1)?;
+        if let Some([r, g, b]) = component.color {
+            write!(writer, ",{:.3},{:.3},{:.3}", r, g, b)?;
+        }
+        if let Some(class) = component.class {
+            write!(writer, ",{}", class)?;
+        }
+        // the Euler number of a connected component is 1 minus the number of its holes
+        if let Some(holes) = component.holes {
+            write!(writer, ",{},{}", holes, 1 - holes as i64)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+fn write_json(writer: &mut impl Write, components: &[Component]) -> io::Result<()> {
+    writeln!(writer, "[")?;
+    for (index, component) in components.iter().enumerate() {
+        write!(writer,
+            "  {{\"label\": {}, \"area\": {}, \"min\": [{}, {}], \"max\": [{}, {}], \"centroid\": [{:.3}, {:.3}]",
+            component.label,
+            component.area,
+            component.min.0,
+            component.min.1,
+            component.max.0,
+            component.max.1,
+            component.centroid.0,
+            component.centroid.1)?;
+        if let Some([r, g, b]) = component.color {
+            write!(writer, ", \"mean_color\": [{:.3}, {:.3}, {:.3}]", r, g, b)?;
+        }
+        if let Some(class) = component.class {
+            write!(writer, ", \"class\": {}", class)?;
+        }
+        if let Some(holes) = component.holes {
+            write!(writer, ", \"holes\": {}, \"euler\": {}", holes, 1 - holes as i64)?;
+        }
+        let separator = if index + 1 < components.len() { "," } else { "" };
+        writeln!(writer, "}}{}", separator)?;
+    }
+    writeln!(writer, "]")
+}
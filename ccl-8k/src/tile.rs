@@ -0,0 +1,284 @@
+use filum::{Context, BufferViewBuilder, PipelineBuilder, DispatchBuilder};
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::options::{Options, Algorithm, Foreground};
+use crate::rule::{self, Rule};
+use crate::shader;
+
+// a rectangle of the image which is labeled on its own
+#[derive(Copy, Clone)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    pub fn len(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+// splits the image into tiles of `size` x `size` pixels, smaller along the right and bottom edges.
+// tiles of the same dimensions are kept together so that the pipelines specialized
+// for them are built once per dimensions.
+pub fn split(width: usize, height: usize, size: usize) -> Vec<Tile> {
+    let mut tiles = vec![];
+    for y in (0..height).step_by(size) {
+        for x in (0..width).step_by(size) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            });
+        }
+    }
+    tiles.sort_by_key(|tile| (tile.width, tile.height));
+    tiles
+}
+
+// labels the image tile by tile with buffers of a single tile,
+// then stitches the components which cross the seams between tiles.
+// the labels are the same as the ones of labeling the whole image at once.
+pub fn label(
+    context: &Arc<Context>,
+    img_view: &image::ImageBuffer<image::Rgba<u8>, std::vec::Vec<u8>>,
+    classes: Option<&[u32]>,
+    options: &Options,
+    shader_dir: Option<&Path>,
+) -> Vec<i32> {
+    let (width, height) = img_view.dimensions();
+    let (width, height) = (width as usize, height as usize);
+    let size = options.tile.unwrap();
+    let tiles = split(width, height, size);
+    let tile_len = size.min(width) * size.min(height);
+    let is_otsu = options.foreground == Foreground::Otsu;
+    info!("{} tiles", tiles.len());
+    let view = BufferViewBuilder::new(context)
+        .layout(
+            bindings!(
+                binding_array!(i32, tile_len),
+                binding_array!(u32, if is_otsu { 256 } else { 1 }),
+                binding_array!(u32, if classes.is_some() { tile_len } else { 1 }),
+            )
+        )
+        .build()
+        .unwrap();
+    let buffer = view.buffer();
+    let foreground = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::FOREGROUND.code(shader_dir))
+        .build()
+        .unwrap();
+    let relabel = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::RELABEL.code(shader_dir))
+        .build()
+        .unwrap();
+    let binding = view.first_binding();
+    // the histogram of the whole image is accumulated over the tiles
+    let otsu_threshold = if is_otsu {
+        info!("histogram");
+        let histogram = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::HISTOGRAM.code(shader_dir))
+            .build()
+            .unwrap();
+        let binding_histogram = view.second_binding();
+        binding_histogram.update_array(|slice| {
+            slice.iter_mut().for_each(|count| *count = 0);
+        });
+        for tile in &tiles {
+            binding.update_array(|slice| crate::input(slice, img_view, tile));
+            histogram.dispatch(tile.len());
+        }
+        let mut threshold = 0;
+        binding_histogram.fetch_array(|slice| {
+            threshold = rule::otsu(slice);
+        });
+        info!("otsu threshold {}", threshold);
+        Some(threshold)
+    } else {
+        None
+    };
+    let rule = Rule::new(options.foreground, options.invert, otsu_threshold);
+    let mut labels = vec![-1; width * height];
+    let mut dimensions = None;
+    let mut pipelines = None;
+    for tile in &tiles {
+        debug!("tile ({}, {}) {}x{}", tile.x, tile.y, tile.width, tile.height);
+        if dimensions != Some((tile.width, tile.height)) {
+            dimensions = Some((tile.width, tile.height));
+            let column = PipelineBuilder::new(buffer)
+                .shader_bytes(shader::COLUMN.code(shader_dir))
+                .specialization(constants!(tile.width as u32, tile.height as u32, classes.is_some() as u32))
+                .build()
+                .unwrap();
+            let merge = PipelineBuilder::new(buffer)
+                .shader_bytes(shader::MERGE.code(shader_dir))
+                .specialization(constants!(tile.width as u32, tile.height as u32, options.connectivity, classes.is_some() as u32))
+                .build()
+                .unwrap();
+            let block = PipelineBuilder::new(buffer)
+                .shader_bytes(shader::BLOCK.code(shader_dir))
+                .specialization(constants!(tile.width as u32, tile.height as u32, options.connectivity, classes.is_some() as u32))
+                .build()
+                .unwrap();
+            let boundary = PipelineBuilder::new(buffer)
+                .shader_bytes(shader::BOUNDARY.code(shader_dir))
+                .specialization(constants!(tile.width as u32, tile.height as u32, options.connectivity, classes.is_some() as u32))
+                .build()
+                .unwrap();
+            pipelines = Some((column, merge, block, boundary));
+        }
+        let (column, merge, block, boundary) = pipelines.as_ref().unwrap();
+        binding.update_array(|slice| crate::input(slice, img_view, tile));
+        if let Some(classes) = classes {
+            view.third_binding().update_array(|slice| {
+                for y in 0..tile.height {
+                    let offset = (tile.y + y) * width + tile.x;
+                    slice[y * tile.width..(y + 1) * tile.width]
+                        .copy_from_slice(&classes[offset..offset + tile.width]);
+                }
+            });
+        }
+        let dispatch = DispatchBuilder::new(&foreground)
+            .workgroup_count(tile.len(), 1, 1)
+            .push_constants(constants!(rule))
+            .build()
+            .unwrap();
+        dispatch.dispatch();
+        match options.algorithm {
+            Algorithm::Strip => {
+                column.dispatch(tile.width);
+                for (n, step_index) in crate::merge_steps(tile.width) {
+                    let dispatch = DispatchBuilder::new(merge)
+                        .workgroup_count(n, 1, 1)
+                        .push_constants(constants!(step_index as u32))
+                        .build()
+                        .unwrap();
+                    dispatch.dispatch();
+                }
+            },
+            Algorithm::Block => {
+                let num_blocks_x = tile.width.div_ceil(crate::BLOCK_WIDTH);
+                let num_blocks_y = tile.height.div_ceil(crate::BLOCK_HEIGHT);
+                for pipeline in [block, boundary].iter() {
+                    let dispatch = DispatchBuilder::new(pipeline)
+                        .workgroup_count(num_blocks_x, num_blocks_y, 1)
+                        .build()
+                        .unwrap();
+                    dispatch.dispatch();
+                }
+            },
+        }
+        relabel.dispatch(tile.len());
+        // labels within the tile are turned into the indices of the same pixels in the image
+        binding.fetch_array(|slice| {
+            for y in 0..tile.height {
+                let offset = (tile.y + y) * width + tile.x;
+                for (x, &label) in slice[y * tile.width..(y + 1) * tile.width].iter().enumerate() {
+                    labels[offset + x] = if label >= 0 {
+                        let label = label as usize;
+                        ((tile.y + label / tile.width) * width + tile.x + label % tile.width) as i32
+                    } else {
+                        label
+                    };
+                }
+            }
+        });
+    }
+    info!("stitching");
+    stitch(&mut labels, width, height, size, options.connectivity, classes);
+    labels
+}
+
+// union-find over labels, where the smallest label of a set is its root
+#[derive(Default)]
+struct Equivalence {
+    parents: HashMap<i32, i32>,
+}
+
+impl Equivalence {
+    fn find(&mut self, label: i32) -> i32 {
+        let mut root = label;
+        while let Some(&parent) = self.parents.get(&root) {
+            root = parent;
+        }
+        // path compression
+        let mut label = label;
+        while label != root {
+            label = self.parents.insert(label, root).unwrap();
+        }
+        root
+    }
+
+    fn union(&mut self, label0: i32, label1: i32) {
+        let root0 = self.find(label0);
+        let root1 = self.find(label1);
+        if root0 != root1 {
+            self.parents.insert(root0.max(root1), root0.min(root1));
+        }
+    }
+}
+
+// joins the components which touch across the seams between tiles.
+// every label is the index of the first pixel of its component within a tile,
+// so the smallest of the joined labels is the first pixel of the whole component.
+fn stitch(labels: &mut [i32], width: usize, height: usize, size: usize, connectivity: u32, classes: Option<&[u32]>) {
+    let mut equivalence = Equivalence::default();
+    let offsets: &[isize] = if connectivity == 8 { &[-1, 0, 1] } else { &[0] };
+    let mut connect = |i0: usize, i1: usize| {
+        let is_same_class = classes.is_none_or(|classes| classes[i0] == classes[i1]);
+        if labels[i0] >= 0 && labels[i1] >= 0 && is_same_class {
+            equivalence.union(labels[i0], labels[i1]);
+        }
+    };
+    // pixels right of a vertical seam with their neighbors on the left
+    for x in (size..width).step_by(size) {
+        for y in 0..height {
+            for &offset in offsets {
+                let y0 = y as isize + offset;
+                if y0 >= 0 && (y0 as usize) < height {
+                    connect(y0 as usize * width + x - 1, y * width + x);
+                }
+            }
+        }
+    }
+    // pixels below a horizontal seam with their neighbors above
+    for y in (size..height).step_by(size) {
+        for x in 0..width {
+            for &offset in offsets {
+                let x0 = x as isize + offset;
+                if x0 >= 0 && (x0 as usize) < width {
+                    connect((y - 1) * width + x0 as usize, y * width + x);
+                }
+            }
+        }
+    }
+    if equivalence.parents.is_empty() {
+        return;
+    }
+    for label in labels.iter_mut().filter(|label| **label >= 0) {
+        *label = equivalence.find(*label);
+    }
+}
+
+// renumbers the components to 0..N-1 in the scan order of their roots like compact.comp,
+// returns the number of components
+pub fn compact(labels: &mut [i32]) -> usize {
+    let mut num_components = 0;
+    for index in 0..labels.len() {
+        let label = labels[index];
+        if label == index as i32 {
+            labels[index] = num_components as i32;
+            num_components += 1;
+        } else if label >= 0 {
+            // the root comes first and has been renumbered already
+            labels[index] = labels[label as usize];
+        }
+    }
+    num_components
+}
@@ -0,0 +1,191 @@
+use filum::Context;
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::batch;
+use crate::options::Options;
+
+// a component of a frame, measured on the host
+struct Region {
+    area: u32,
+    sum: (u64, u64),
+    min: (usize, usize),
+    max: (usize, usize),
+}
+
+// a row of the trajectory CSV
+struct Point {
+    track: u32,
+    frame: usize,
+    label: i32,
+    area: u32,
+    centroid: (f64, f64),
+    min: (usize, usize),
+    max: (usize, usize),
+}
+
+// labels every frame with `label_images`, a single buffer view and a single set of pipelines,
+// then gives every component the track of the component of the previous frame
+// it overlaps the most. the pairs of components which overlap the most are matched first,
+// so that on a split the largest part keeps the track and on a merge the largest part passes it on.
+// components without a match start new tracks.
+pub fn run(context: &Arc<Context>, frames: Vec<PathBuf>, options: &Options, shader_dir: Option<&Path>) {
+    info!("{} frames", frames.len());
+    // every frame is needed to follow the components
+    let mut images = batch::decode(frames, options.classes)
+        .into_iter()
+        .map(|image| {
+            image.unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                process::exit(1);
+            })
+        })
+        .peekable();
+    let dimensions = images.peek().unwrap().img.dimensions();
+    let instant = Instant::now();
+    let mut previous: Option<(Vec<i32>, HashMap<i32, u32>)> = None;
+    let mut num_tracks = 0;
+    let mut points = vec![];
+    let mut frame = 0;
+    crate::label_images(context, &mut images, options, shader_dir, |image, labels, options| {
+        let path = image.path.as_ref().unwrap();
+        debug!("frame {} {}", frame, path.display());
+        let width = image.img.width() as usize;
+        let regions = measure(labels, width);
+        let tracks = match &previous {
+            Some((previous_labels, previous_tracks)) => {
+                associate(previous_labels, previous_tracks, labels, &regions, &mut num_tracks)
+            },
+            None => regions.keys()
+                .map(|&label| {
+                    num_tracks += 1;
+                    (label, num_tracks - 1)
+                })
+                .collect(),
+        };
+        debug!("{} components, {} tracks so far", regions.len(), num_tracks);
+        for (&label, region) in &regions {
+            points.push(Point {
+                track: tracks[&label],
+                frame,
+                label,
+                area: region.area,
+                centroid: (
+                    region.sum.0 as f64 / region.area as f64,
+                    region.sum.1 as f64 / region.area as f64,
+                ),
+                min: region.min,
+                max: region.max,
+            });
+        }
+        if let Some(dir) = &options.track_frames {
+            // components keep the color of their track over the frames
+            for (pixel, &label) in image.img.pixels_mut().zip(labels) {
+                if label >= 0 {
+                    *pixel = crate::preview::color(tracks[&label] as i32);
+                }
+            }
+            let name = path.file_stem().unwrap().to_string_lossy();
+            let path = dir.join(format!("{}.png", name));
+            if let Err(err) = image.img.save_with_format(&path, image::ImageFormat::Png) {
+                eprintln!("failed to save {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        }
+        previous = Some((labels.to_vec(), tracks));
+        frame += 1;
+    });
+    // `label_images` stops at the first frame of other dimensions
+    if let Some(image) = images.peek() {
+        let (width, height) = image.img.dimensions();
+        eprintln!("{} is {}x{}, expected {}x{} like the first frame",
+            image.path.as_ref().unwrap().display(), width, height, dimensions.0, dimensions.1);
+        process::exit(1);
+    }
+    info!("done {:?}, {} tracks", instant.elapsed(), num_tracks);
+    let path = options.track.as_ref().unwrap();
+    // trajectories are listed track by track
+    points.sort_by_key(|point| (point.track, point.frame));
+    if let Err(err) = save(path, &points) {
+        eprintln!("failed to save {}: {}", path.display(), err);
+        process::exit(1);
+    }
+    info!("trajectories saved as {}", path.display());
+}
+
+// measures every component by its label, in the order of labels
+fn measure(labels: &[i32], width: usize) -> BTreeMap<i32, Region> {
+    let mut regions: BTreeMap<i32, Region> = BTreeMap::new();
+    for (index, &label) in labels.iter().enumerate() {
+        if label < 0 {
+            continue;
+        }
+        let (x, y) = (index % width, index / width);
+        let region = regions.entry(label).or_insert(Region {
+            area: 0,
+            sum: (0, 0),
+            min: (x, y),
+            max: (x, y),
+        });
+        region.area += 1;
+        region.sum.0 += x as u64;
+        region.sum.1 += y as u64;
+        region.min = (region.min.0.min(x), region.min.1.min(y));
+        region.max = (region.max.0.max(x), region.max.1.max(y));
+    }
+    regions
+}
+
+// returns the track of every component of the current frame
+fn associate(
+    previous_labels: &[i32],
+    previous_tracks: &HashMap<i32, u32>,
+    labels: &[i32],
+    regions: &BTreeMap<i32, Region>,
+    num_tracks: &mut u32,
+) -> HashMap<i32, u32> {
+    let mut overlaps: HashMap<(i32, i32), u32> = HashMap::new();
+    for (&previous, &label) in previous_labels.iter().zip(labels) {
+        if previous >= 0 && label >= 0 {
+            *overlaps.entry((previous, label)).or_insert(0) += 1;
+        }
+    }
+    // the largest overlaps first, ties are broken by labels so that the result is reproducible
+    let mut pairs: Vec<((i32, i32), u32)> = overlaps.into_iter().collect();
+    pairs.sort_by_key(|&((previous, label), overlap)| (Reverse(overlap), previous, label));
+    let mut tracks = HashMap::new();
+    let mut continued = HashSet::new();
+    for ((previous, label), _) in pairs {
+        if tracks.contains_key(&label) || continued.contains(&previous) {
+            continue;
+        }
+        tracks.insert(label, previous_tracks[&previous]);
+        continued.insert(previous);
+    }
+    for &label in regions.keys() {
+        tracks.entry(label).or_insert_with(|| {
+            *num_tracks += 1;
+            *num_tracks - 1
+        });
+    }
+    tracks
+}
+
+fn save(path: &Path, points: &[Point]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "track,frame,label,area,centroid_x,centroid_y,min_x,min_y,max_x,max_y")?;
+    for point in points {
+        writeln!(writer, "{},{},{},{},{:.3},{:.3},{},{},{},{}",
+            point.track, point.frame, point.label, point.area,
+            point.centroid.0, point.centroid.1,
+            point.min.0, point.min.1, point.max.0, point.max.1)?;
+    }
+    writer.flush()
+}
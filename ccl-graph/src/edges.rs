@@ -0,0 +1,72 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct Graph {
+    pub num_vertices: usize,
+    // the two ends of every edge one after another
+    pub edges: Vec<u32>,
+}
+
+impl Graph {
+    pub fn num_edges(&self) -> usize {
+        self.edges.len() / 2
+    }
+}
+
+// reads an edge list, the format is chosen by the extension of the path.
+// `.bin` holds pairs of little-endian u32 vertices.
+// any other file is text with a pair of vertices per line separated by a comma,
+// spaces or a tab. empty lines, lines starting with # or % and a header line are skipped.
+// vertices are numbered from 0, the vertices of no edge are components of their own.
+pub fn load(path: &Path, num_vertices: Option<usize>) -> io::Result<Graph> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let edges = if path.extension() == Some("bin".as_ref()) {
+        let bytes = fs::read(path)?;
+        if bytes.len() % 8 != 0 {
+            return Err(invalid(format!("{} bytes is not a whole number of edges of 8 bytes", bytes.len())));
+        }
+        bytes.chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect()
+    } else {
+        parse(&fs::read_to_string(path)?).map_err(invalid)?
+    };
+    // labels are i32 on the GPU
+    let max_vertices = i32::MAX as usize;
+    let num_vertices = match num_vertices {
+        Some(n) => {
+            if let Some(&vertex) = edges.iter().find(|&&vertex| vertex as usize >= n) {
+                return Err(invalid(format!("vertex {} is out of the {} vertices", vertex, n)));
+            }
+            n
+        },
+        None => edges.iter().max().map_or(0, |&vertex| vertex as usize + 1),
+    };
+    if num_vertices > max_vertices {
+        return Err(invalid(format!("{} vertices are more than {}", num_vertices, max_vertices)));
+    }
+    Ok(Graph { num_vertices, edges })
+}
+
+fn parse(text: &str) -> Result<Vec<u32>, String> {
+    let mut edges = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('%') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        let vertices: Result<Vec<u32>, _> = fields.iter().take(2).map(|field| field.parse::<u32>()).collect();
+        match vertices {
+            // further fields such as weights are ignored
+            Ok(vertices) if vertices.len() == 2 => edges.extend(vertices),
+            // a header such as `source,target`
+            Err(_) if edges.is_empty() && fields.iter().all(|field| field.parse::<f64>().is_err()) => continue,
+            _ => return Err(format!("line {} is not a pair of vertices: {}", index + 1, line)),
+        }
+    }
+    Ok(edges)
+}
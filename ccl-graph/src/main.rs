@@ -0,0 +1,160 @@
+#[macro_use]
+extern crate filum;
+use filum::{Context, BufferViewBuilder, PipelineBuilder, DispatchBuilder};
+
+mod edges;
+mod options;
+mod shader;
+
+use edges::Graph;
+use options::Options;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+use std::time::Instant;
+
+// invocations of a workgroup of hook.comp and relabel.comp
+const WORKGROUP_SIZE: usize = 256;
+// workgroups of a dispatch which every Vulkan device supports
+const MAX_WORKGROUPS: usize = 65535;
+
+// ccl-simple without the grid.
+// every vertex starts as the root of its own component, then the edges hook
+// the roots of their ends together with the atomic union-find of merge.comp.
+fn main() {
+    let options = Options::from_args();
+    let shader_dir = options.shader_dir.as_deref();
+    println!("loading edges");
+    let graph = edges::load(&options.input, options.vertices).unwrap_or_else(|err| {
+        eprintln!("failed to load {}: {}", options.input.display(), err);
+        process::exit(1);
+    });
+    let (num_vertices, num_edges) = (graph.num_vertices, graph.num_edges());
+    println!("{} vertices, {} edges", num_vertices, num_edges);
+    if num_vertices == 0 {
+        eprintln!("the graph has no vertices");
+        process::exit(1);
+    }
+    if num_vertices > MAX_WORKGROUPS * WORKGROUP_SIZE {
+        eprintln!("graphs of more than {} vertices are not supported", MAX_WORKGROUPS * WORKGROUP_SIZE);
+        process::exit(1);
+    }
+    // the edges are uploaded in chunks so that a graph of any size fits in the buffer
+    // and every chunk is hooked in a single dispatch
+    let chunk = options.chunk.min(num_edges).clamp(1, MAX_WORKGROUPS * WORKGROUP_SIZE);
+    let context = Context::new().unwrap();
+    let view = BufferViewBuilder::new(&context)
+        .layout(
+            bindings!(
+                binding_array!(i32, num_vertices),
+                binding_array!(u32, chunk * 2),
+            )
+        )
+        .build()
+        .unwrap();
+    let buffer = view.buffer();
+    let hook = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::HOOK.code(shader_dir))
+        .build()
+        .unwrap();
+    let relabel = PipelineBuilder::new(buffer)
+        .shader_bytes(shader::RELABEL.code(shader_dir))
+        .build()
+        .unwrap();
+    let mut instant = Instant::now();
+    let binding_labels = view.first_binding();
+    binding_labels.update_array(|slice| {
+        for (index, elem) in slice.iter_mut().enumerate() {
+            *elem = index as i32;
+        }
+        instant = Instant::now();
+    });
+    println!("hook");
+    let binding_edges = view.second_binding();
+    for edges in graph.edges.chunks(chunk * 2) {
+        binding_edges.update_array(|slice| slice[..edges.len()].copy_from_slice(edges));
+        let n = edges.len() / 2;
+        let dispatch = DispatchBuilder::new(&hook)
+            .workgroup_count(n.div_ceil(WORKGROUP_SIZE), 1, 1)
+            .push_constants(constants!(n as u32))
+            .build()
+            .unwrap();
+        dispatch.dispatch();
+    }
+    println!("relabel");
+    let dispatch = DispatchBuilder::new(&relabel)
+        .workgroup_count(num_vertices.div_ceil(WORKGROUP_SIZE), 1, 1)
+        .push_constants(constants!(num_vertices as u32))
+        .build()
+        .unwrap();
+    dispatch.dispatch();
+    binding_labels.fetch_array(|slice| {
+        println!("done {:?}", instant.elapsed());
+        if options.verify {
+            verify(slice, &graph);
+        }
+        if let Err(err) = output(slice, &options) {
+            eprintln!("failed to save output: {}", err);
+            process::exit(1);
+        }
+    });
+}
+
+// compares the components with the ones of a CPU union-find, exits on the first vertex which disagrees.
+// both label every component with its smallest vertex, so the labels must be equal.
+fn verify(labels: &[i32], graph: &Graph) {
+    let mut parents: Vec<usize> = (0..graph.num_vertices).collect();
+    let find = |parents: &mut Vec<usize>, mut vertex: usize| {
+        while parents[vertex] != vertex {
+            parents[vertex] = parents[parents[vertex]];
+            vertex = parents[vertex];
+        }
+        vertex
+    };
+    for edge in graph.edges.chunks(2) {
+        let root0 = find(&mut parents, edge[0] as usize);
+        let root1 = find(&mut parents, edge[1] as usize);
+        parents[root0.max(root1)] = root0.min(root1);
+    }
+    for (vertex, &label) in labels.iter().enumerate() {
+        let expected = find(&mut parents, vertex) as i32;
+        if label != expected {
+            eprintln!("verification failed: vertex {} is in component {} where the reference has {}",
+                vertex, label, expected);
+            process::exit(1);
+        }
+    }
+    println!("verified");
+}
+
+fn output(labels: &[i32], options: &Options) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(&options.output)?);
+    if options.output.extension() == Some("csv".as_ref()) {
+        writeln!(writer, "vertex,component")?;
+        for (vertex, label) in labels.iter().enumerate() {
+            writeln!(writer, "{},{}", vertex, label)?;
+        }
+    } else {
+        for label in labels {
+            writer.write_all(&label.to_le_bytes())?;
+        }
+    }
+    writer.flush()?;
+    println!("components saved as {}", options.output.display());
+    let mut sizes: BTreeMap<i32, u64> = BTreeMap::new();
+    for &label in labels {
+        *sizes.entry(label).or_insert(0) += 1;
+    }
+    let mut writer = BufWriter::new(File::create(&options.sizes)?);
+    writeln!(writer, "component,vertices")?;
+    for (label, size) in &sizes {
+        writeln!(writer, "{},{}", label, size)?;
+    }
+    writer.flush()?;
+    let largest = sizes.values().max().unwrap();
+    println!("{} components, the largest of {} vertices, sizes saved as {}",
+        sizes.len(), largest, options.sizes.display());
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+usage: filum-example-ccl-graph [OPTIONS] <EDGES>
+
+Finds the connected components of an undirected graph given as an edge list.
+EDGES ending with .bin holds pairs of little-endian u32 vertices, any other file
+is text with a pair of vertices per line separated by a comma, spaces or a tab.
+Vertices are numbered from 0 and every component is labeled with its smallest vertex.
+
+options:
+    -n, --vertices <N>          number of vertices, the ones of no edge are components
+                                of their own [default: largest vertex + 1]
+    -o, --output <PATH>         component of every vertex, as CSV when PATH ends with .csv,
+                                raw little-endian i32 otherwise [default: components.csv]
+        --sizes <PATH>          number of vertices of every component as CSV [default: sizes.csv]
+        --chunk <N>             edges uploaded and hooked per dispatch [default: 4194304]
+        --verify                checks the components against a CPU union-find
+        --shader-dir <DIR>      loads <DIR>/*.comp.spv instead of the embedded shaders
+    -h, --help                  prints this message
+";
+
+pub struct Options {
+    pub input: PathBuf,
+    pub vertices: Option<usize>,
+    pub output: PathBuf,
+    pub sizes: PathBuf,
+    pub chunk: usize,
+    pub verify: bool,
+    pub shader_dir: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        let mut input = None;
+        let mut options = Options {
+            input: PathBuf::new(),
+            vertices: None,
+            output: PathBuf::from("components.csv"),
+            sizes: PathBuf::from("sizes.csv"),
+            chunk: 1 << 22,
+            verify: false,
+            shader_dir: None,
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-n" | "--vertices" => options.vertices = Some(number(&value(&mut args, &arg))),
+                "-o" | "--output" => options.output = PathBuf::from(value(&mut args, &arg)),
+                "--sizes" => options.sizes = PathBuf::from(value(&mut args, &arg)),
+                "--chunk" => options.chunk = match number(&value(&mut args, &arg)) {
+                    0 => fail("--chunk requires at least 1"),
+                    chunk => chunk,
+                },
+                "--verify" => options.verify = true,
+                "--shader-dir" => options.shader_dir = Some(PathBuf::from(value(&mut args, &arg))),
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ if arg.starts_with('-') => fail(&format!("unknown option {}", arg)),
+                _ if input.is_none() => input = Some(PathBuf::from(arg)),
+                _ => fail("only one input can be given"),
+            }
+        }
+        options.input = input.unwrap_or_else(|| fail("no input is given"));
+        options
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, name: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| fail(&format!("{} requires a value", name)))
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> T {
+    value.parse()
+        .unwrap_or_else(|_| fail(&format!("invalid number {}", value)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,28 @@
+use std::fs;
+use std::path::Path;
+use std::process;
+
+// SPIR-V module compiled from ./data by the build script
+pub struct Shader {
+    pub name: &'static str,
+    pub code: &'static [u8],
+}
+
+include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+
+impl Shader {
+    // returns the SPIR-V code to be passed to `PipelineBuilder::shader_bytes`.
+    // `dir` overrides the embedded module with `<dir>/<name>.comp.spv`.
+    pub fn code(&self, dir: Option<&Path>) -> Vec<u8> {
+        match dir {
+            Some(dir) => {
+                let path = dir.join(self.name);
+                fs::read(&path).unwrap_or_else(|err| {
+                    eprintln!("failed to read {}: {}", path.display(), err);
+                    process::exit(1);
+                })
+            },
+            None => self.code.to_vec(),
+        }
+    }
+}
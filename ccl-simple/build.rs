@@ -0,0 +1,55 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command};
+
+// compiles all the GLSL files in ./data into SPIR-V
+// and generates `shaders.rs` which embeds the results.
+// set GLSLC to use a compiler other than the one found in PATH.
+fn main() {
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let glslc = env::var_os("GLSLC").unwrap_or_else(|| "glslc".into());
+    println!("cargo:rerun-if-env-changed=GLSLC");
+    println!("cargo:rerun-if-changed=data");
+    let mut sources: Vec<PathBuf> = fs::read_dir("data")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("comp".as_ref()))
+        .collect();
+    sources.sort();
+    let mut module = String::new();
+    for source in &sources {
+        println!("cargo:rerun-if-changed={}", source.display());
+        let file_name = source.file_name().unwrap().to_str().unwrap();
+        let output = out_dir.join(format!("{}.spv", file_name));
+        let result = Command::new(&glslc)
+            .arg("-O")
+            .arg("-c")
+            .arg(source)
+            // shaders include the shared parts such as unionfind.glsl from data
+            .arg("-I")
+            .arg("data")
+            .arg("--target-env=vulkan1.1")
+            .arg("--target-spv=spv1.3")
+            .arg("-o")
+            .arg(&output)
+            .output()
+            .unwrap_or_else(|err| {
+                eprintln!("failed to run {:?}: {}", glslc, err);
+                process::exit(1);
+            });
+        if !result.status.success() {
+            // glslc reports errors as `data/xyz.comp:LINE: error: ...`
+            eprintln!("failed to compile {}", source.display());
+            eprint!("{}", String::from_utf8_lossy(&result.stderr));
+            process::exit(1);
+        }
+        let name = file_name.trim_end_matches(".comp").replace('-', "_").to_uppercase();
+        module.push_str(&format!(
+            "pub const {}: Shader = Shader {{ name: {:?}, code: include_bytes!({:?}) }};\n",
+            name,
+            format!("{}.spv", file_name),
+            output));
+    }
+    fs::write(out_dir.join("shaders.rs"), module).unwrap();
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub foreground: Vec<bool>,
+}
+
+// reads a grid from a text file, or from stdin when the path is `-`.
+// every non-empty line is a row. a row with commas, such as the labels printed by this example,
+// or of integers separated by spaces, holds one cell per value, negative numbers and `.` are background.
+// any other row holds one cell per character, such as `0110`, `#..#` or `# #`,
+// where `0`, `.` and spaces are background.
+pub fn read(path: &Path) -> io::Result<Grid> {
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(path)?
+    };
+    parse(&text).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+// parses a grid in the format of `read`
+pub fn parse(text: &str) -> Result<Grid, String> {
+    let mut rows: Vec<Vec<bool>> = vec![];
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut cells = line.split_whitespace();
+        let is_numeric = cells.clone().count() > 1 && cells.all(|cell| cell.parse::<i64>().is_ok());
+        let row: Vec<bool> = if line.contains(',') || is_numeric {
+            line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|cell| !cell.is_empty())
+                .map(|cell| !(cell == "." || cell.parse::<i64>().is_ok_and(|value| value < 0)))
+                .collect()
+        } else {
+            line.chars()
+                .map(|c| !(c == '0' || c == '.' || c.is_whitespace()))
+                .collect()
+        };
+        if let Some(first) = rows.first() {
+            if row.len() != first.len() {
+                return Err(format!("row {} has {} cells where the first one has {}", rows.len() + 1, row.len(), first.len()));
+            }
+        }
+        rows.push(row);
+    }
+    if rows.is_empty() {
+        return Err("no rows".to_string());
+    }
+    Ok(Grid {
+        width: rows[0].len(),
+        height: rows.len(),
+        foreground: rows.concat(),
+    })
+}
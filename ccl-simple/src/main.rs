@@ -3,77 +3,169 @@
 extern crate filum;
 use filum::{Context, BufferViewBuilder, PipelineBuilder, DispatchBuilder};
 
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+
+mod grid;
+#[cfg(test)]
+mod masks;
+mod options;
+mod percolation;
+mod reference;
+mod shader;
+#[cfg(test)]
+mod tests;
+
+use options::Options;
+
 // A Parallel Approach to Object Identification in Large-scale Images
 // @see https://www.academia.edu/29842500/
 fn main() {
-    let dim = (8usize, 8usize);
-    let table: Vec<i32> = vec![
-        0, 0, 0, 0, 0, 0, 0, 0,
-        0, 1, 1, 0, 1, 1, 1, 0,
-        0, 1, 1, 0, 1, 1, 1, 0,
-        1, 1, 1, 0, 0, 0, 0, 1,
-        0, 1, 1, 1, 0, 0, 0, 1,
-        0, 0, 0, 0, 0, 1, 1, 0,
-        0, 1, 0, 1, 1, 1, 1, 0,
-        0, 1, 1, 1, 0, 0, 0, 0,
-    ];
-    let mut table: Vec<i32> = table.into_iter()
+    let options = Options::from_args();
+    let grid = match &options.input {
+        Some(path) => grid::read(path).unwrap_or_else(|err| {
+            eprintln!("failed to read {}: {}", path.display(), err);
+            process::exit(1);
+        }),
+        None => grid::parse(include_str!("../res/table.txt")).unwrap(),
+    };
+    let (dim, foreground) = ((grid.width, grid.height), grid.foreground);
+    let table: Vec<i32> = foreground.iter()
         .enumerate()
-        .map(|(i, v)| if v == 0 { -1i32 } else { i as i32 })
+        .map(|(i, &v)| if v { i as i32 } else { -1 })
         .collect();
     let len = table.len();
     assert_eq!(len, dim.0 * dim.1);
-    assert!(dim.0.is_power_of_two());
     let context = Context::new().unwrap();
-    let buffer_view = BufferViewBuilder::new(&context)
+    for (n, step_index) in merge_steps(dim.0) {
+        println!("n {}, si {}", n, step_index);
+    }
+    let shader_dir = options.shader_dir.as_deref();
+    let table = label(&context, &table, dim, options.connectivity, options.periodic, options.repeat, shader_dir)
+        .unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            process::exit(1);
+        });
+    // output
+    dump(&table, dim.0);
+    if options.percolation {
+        let spanning = if options.periodic {
+            percolation::wrapping(&table, dim.0, dim.1, options.connectivity)
+        } else {
+            percolation::spanning(&table, dim.0, dim.1)
+        };
+        print!("{}", spanning);
+    }
+    if options.verify {
+        let expected = reference::label(&foreground, dim.0, dim.1, options.connectivity, options.periodic);
+        match reference::compare(&table, &expected, dim.0) {
+            Ok(num_components) => println!("verified {} components", num_components),
+            Err(mismatch) => {
+                eprintln!("verification failed: {}", mismatch);
+                process::exit(1);
+            },
+        }
+    }
+}
+
+// labels `table`, where foreground pixels hold their own index and the background -1.
+// `periodic` also joins the pixels across the opposite edges.
+// labels it `repeat` times with the same pipelines and checks that every run gives exactly
+// the same labels, whatever order the invocations of merge.comp run in.
+fn label(
+    context: &Arc<Context>,
+    table: &[i32],
+    dim: (usize, usize),
+    connectivity: u32,
+    periodic: bool,
+    repeat: usize,
+    shader_dir: Option<&Path>,
+) -> Result<Vec<i32>, String> {
+    let len = table.len();
+    let buffer_view = BufferViewBuilder::new(context)
         .bind_array::<i32>(len)
         .build()
         .unwrap();
     let buffer = buffer_view.buffer();
     let column = PipelineBuilder::new(buffer)
-        .shader("data/column.comp.spv")
+        .shader_bytes(shader::COLUMN.code(shader_dir))
         .specialization(constants!(dim.0 as u32, dim.1 as u32))
         .build()
         .unwrap();
     let merge = PipelineBuilder::new(buffer)
-        .shader("data/merge.comp.spv")
-        .specialization(constants!(dim.0 as u32, dim.1 as u32))
+        .shader_bytes(shader::MERGE.code(shader_dir))
+        .specialization(constants!(dim.0 as u32, dim.1 as u32, connectivity))
         .build()
         .unwrap();
     let relabel = PipelineBuilder::new(buffer)
-        .shader("data/relabel.comp.spv")
+        .shader_bytes(shader::RELABEL.code(shader_dir))
         .build()
         .unwrap();
+    let wrap = if periodic {
+        let wrap = PipelineBuilder::new(buffer)
+            .shader_bytes(shader::WRAP.code(shader_dir))
+            .specialization(constants!(dim.0 as u32, dim.1 as u32, connectivity))
+            .build()
+            .unwrap();
+        Some(wrap)
+    } else {
+        None
+    };
     let binding = buffer_view.binding();
-    binding.update_array_copying(&table);
-    // column
-    column.dispatch(dim.0);
-    // merge
-    {
-        let mut step_index = 0;
-        let mut n = dim.0 >> 1;
-        while n != 0 {
-            println!("n {}, si {}", n, step_index);
+    let mut first = vec![0; len];
+    let mut labels = vec![0; len];
+    for run in 0..repeat {
+        binding.update_array_copying(table);
+        // column
+        column.dispatch(dim.0);
+        // merge
+        for (n, step_index) in merge_steps(dim.0) {
             let dispatch = DispatchBuilder::new(&merge)
                 .workgroup_count(n, 1, 1)
                 .push_constants(constants!(step_index as u32))
                 .build()
                 .unwrap();
             dispatch.dispatch();
-            n = n >> 1;
-            step_index += 1;
+        }
+        // wrap
+        if let Some(wrap) = &wrap {
+            wrap.dispatch(dim.0 + dim.1);
+        }
+        // relabel
+        relabel.dispatch(len);
+        if run == 0 {
+            binding.fetch_array_copying(&mut first);
+            continue;
+        }
+        binding.fetch_array_copying(&mut labels);
+        if let Some(index) = labels.iter().zip(&first).position(|(label, expected)| label != expected) {
+            return Err(format!("run {} differs from the first one at pixel ({}, {}): {} instead of {}",
+                run, index % dim.0, index / dim.0, labels[index], first[index]));
         }
     }
-    // relabel
-    relabel.dispatch(len);
-    binding.fetch_array_copying(&mut table);
-    // output
-    dump(&table, dim.0);
+    Ok(first)
+}
+
+// every step merges pairs of adjacent strips of `1 << step_index` columns.
+// the last strip is left for the next step when it has no pair.
+// returns the number of pairs and the step index of every step.
+fn merge_steps(width: usize) -> Vec<(usize, usize)> {
+    let mut steps = vec![];
+    let mut step_index = 0;
+    while (1 << step_index) < width {
+        let stride = 2 << step_index;
+        steps.push(((width - (1 << step_index)).div_ceil(stride), step_index));
+        step_index += 1;
+    }
+    steps
 }
 
+// prints the labels in columns as wide as the longest label, 3 characters at least
 fn dump(v: &[i32], dim_x: usize) {
+    let width = v.iter().map(|v| v.to_string().len()).max().unwrap_or(0).max(3);
     let lines: Vec<String> = v.iter()
-        .map(|v| format!("{:3}, ", v))
+        .map(|v| format!("{:width$}, ", v, width = width))
         .collect();
     let new_line = "\n".to_string();
     let lines: String = lines.chunks(dim_x)
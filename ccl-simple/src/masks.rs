@@ -0,0 +1,117 @@
+use std::fmt;
+
+// xorshift64*, enough to generate reproducible masks without a dependency
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // the state must not be zero
+        Random(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum Shape {
+    // every pixel is foreground with the same probability
+    Noise(f64),
+    // a single path winding inward, which joins strips at every merge step
+    Spiral,
+    // every pixel touches the others only diagonally
+    Checkerboard,
+    // full rows joined alternately at their right and left ends
+    Serpentine,
+    Full,
+    Empty,
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Shape::Noise(density) => write!(f, "noise {:.2}", density),
+            Shape::Spiral => write!(f, "spiral"),
+            Shape::Checkerboard => write!(f, "checkerboard"),
+            Shape::Serpentine => write!(f, "serpentine"),
+            Shape::Full => write!(f, "full"),
+            Shape::Empty => write!(f, "empty"),
+        }
+    }
+}
+
+pub struct Mask {
+    pub shape: Shape,
+    pub width: usize,
+    pub height: usize,
+    pub foreground: Vec<bool>,
+}
+
+// generates a mask of `shape` and random dimensions.
+// most of the masks are small, some are wide enough to take several merge steps.
+pub fn generate(shape: Shape, random: &mut Random) -> Mask {
+    let limit = if random.chance(0.2) { 256 } else { 32 };
+    let width = 1 + random.below(limit);
+    let height = 1 + random.below(limit);
+    let len = width * height;
+    let foreground = match shape {
+        Shape::Noise(density) => (0..len).map(|_| random.chance(density)).collect(),
+        Shape::Spiral => spiral(width, height),
+        Shape::Checkerboard => (0..len).map(|index| (index % width + index / width).is_multiple_of(2)).collect(),
+        Shape::Serpentine => (0..len)
+            .map(|index| {
+                let (x, y) = (index % width, index / width);
+                y % 2 == 0 || (y % 4 == 1 && x + 1 == width) || (y % 4 == 3 && x == 0)
+            })
+            .collect(),
+        Shape::Full => vec![true; len],
+        Shape::Empty => vec![false; len],
+    };
+    Mask { shape, width, height, foreground }
+}
+
+// walks clockwise from the top left corner, turning whenever the path
+// would come next to itself, so that the turns keep a gap of one pixel.
+fn spiral(width: usize, height: usize) -> Vec<bool> {
+    let mut foreground = vec![false; width * height];
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let is_drawn = |foreground: &[bool], x: isize, y: isize| {
+        x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height
+            && foreground[y as usize * width + x as usize]
+    };
+    let can_move = |foreground: &[bool], (x, y): (isize, isize), (dx, dy): (isize, isize)| {
+        let (nx, ny) = (x + dx, y + dy);
+        nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height
+            && !is_drawn(foreground, nx, ny)
+            && !is_drawn(foreground, nx + dx, ny + dy)
+    };
+    let mut position = (0, 0);
+    let mut direction = 0;
+    foreground[0] = true;
+    loop {
+        if !can_move(&foreground, position, directions[direction]) {
+            direction = (direction + 1) % 4;
+            if !can_move(&foreground, position, directions[direction]) {
+                break;
+            }
+        }
+        let (dx, dy) = directions[direction];
+        position = (position.0 + dx, position.1 + dy);
+        foreground[position.1 as usize * width + position.0 as usize] = true;
+    }
+    foreground
+}
@@ -0,0 +1,90 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+usage: filum-example-ccl-simple [OPTIONS] [INPUT]
+
+Labels the connected components of a small binary table and prints the labels.
+INPUT is a text file of the table, or - for stdin, instead of the built-in 8x8 one.
+Every line is a row of one cell per character, such as 0110 or #..#, or of cells
+separated by spaces or commas. 0, . and negative numbers are background.
+
+options:
+    -c, --connectivity <N>      pixel connectivity: 4 or 8 [default: 4]
+        --periodic              joins the left edge with the right one and the top edge
+                                with the bottom one, as on a torus
+        --percolation           reports the first cluster which spans the table horizontally
+                                and vertically, with --periodic the first one which wraps
+                                around the torus
+        --verify                checks the labels against a CPU union-find
+        --repeat <N>            labels every table N times and checks that the runs agree
+                                [default: 1]
+        --shader-dir <DIR>      loads <DIR>/*.comp.spv instead of the embedded shaders
+    -h, --help                  prints this message
+";
+
+pub struct Options {
+    pub input: Option<PathBuf>,
+    pub connectivity: u32,
+    pub periodic: bool,
+    pub percolation: bool,
+    pub verify: bool,
+    pub repeat: usize,
+    pub shader_dir: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        let mut options = Options {
+            input: None,
+            connectivity: 4,
+            periodic: false,
+            percolation: false,
+            verify: false,
+            repeat: 1,
+            shader_dir: None,
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" | "--connectivity" => options.connectivity = match value(&mut args, &arg).as_str() {
+                    "4" => 4,
+                    "8" => 8,
+                    other => fail(&format!("invalid connectivity {}", other)),
+                },
+                "--periodic" => options.periodic = true,
+                "--percolation" => options.percolation = true,
+                "--verify" => options.verify = true,
+                "--repeat" => options.repeat = match number(&value(&mut args, &arg)) {
+                    0 => fail("--repeat requires at least 1"),
+                    repeat => repeat,
+                },
+                "--shader-dir" => options.shader_dir = Some(PathBuf::from(value(&mut args, &arg))),
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ if arg.starts_with('-') && arg != "-" => fail(&format!("unknown argument {}", arg)),
+                _ if options.input.is_none() => options.input = Some(PathBuf::from(arg)),
+                _ => fail("only one input can be given"),
+            }
+        }
+        options
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, name: &str) -> String {
+    args.next()
+        .unwrap_or_else(|| fail(&format!("{} requires a value", name)))
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> T {
+    value.parse()
+        .unwrap_or_else(|_| fail(&format!("invalid number {}", value)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
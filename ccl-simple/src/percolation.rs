@@ -0,0 +1,87 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+// the first clusters in scan order which span the lattice in either direction
+pub struct Spanning {
+    pub horizontal: Option<i32>,
+    pub vertical: Option<i32>,
+    // whether the clusters wrap around a torus rather than touch opposite edges
+    pub periodic: bool,
+}
+
+impl fmt::Display for Spanning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = if self.periodic { "wraps around" } else { "spans" };
+        for (direction, label) in [("horizontally", self.horizontal), ("vertically", self.vertical)].iter() {
+            match label {
+                Some(label) => writeln!(f, "cluster {} {} {}", label, verb, direction)?,
+                None => writeln!(f, "no cluster {} {}", verb, direction)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// with open boundaries, a cluster spans the lattice when it touches both opposite edges
+pub fn spanning(labels: &[i32], width: usize, height: usize) -> Spanning {
+    let column = |x: usize| (0..height).map(move |y| labels[y * width + x]).collect::<HashSet<_>>();
+    let row = |y: usize| (0..width).map(move |x| labels[y * width + x]).collect::<HashSet<_>>();
+    let first = |edge0: HashSet<i32>, edge1: HashSet<i32>| {
+        edge0.intersection(&edge1).copied().filter(|&label| label >= 0).min()
+    };
+    Spanning {
+        horizontal: first(column(0), column(width - 1)),
+        vertical: first(row(0), row(height - 1)),
+        periodic: false,
+    }
+}
+
+// with periodic boundaries, every cluster which touches an edge continues on the opposite one,
+// so a cluster spans the lattice only when it wraps around the torus.
+// walks every cluster keeping the position of every pixel as if the lattice were tiled
+// over the plane, a cluster wraps around when it reaches one of its pixels
+// again at a position shifted by the width or the height.
+pub fn wrapping(labels: &[i32], width: usize, height: usize, connectivity: u32) -> Spanning {
+    let offsets: &[(i64, i64)] = if connectivity == 8 {
+        &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)]
+    } else {
+        &[(1, 0), (-1, 0), (0, 1), (0, -1)]
+    };
+    let (w, h) = (width as i64, height as i64);
+    let mut positions: Vec<Option<(i64, i64)>> = vec![None; labels.len()];
+    let mut spanning = Spanning { horizontal: None, vertical: None, periodic: true };
+    for start in 0..labels.len() {
+        let label = labels[start];
+        if label < 0 || positions[start].is_some() {
+            continue;
+        }
+        positions[start] = Some(((start % width) as i64, (start / width) as i64));
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(index) = queue.pop_front() {
+            let (x, y) = positions[index].unwrap();
+            for &(dx, dy) in offsets {
+                let (nx, ny) = (x + dx, y + dy);
+                let neighbor = (ny.rem_euclid(h) * w + nx.rem_euclid(w)) as usize;
+                if labels[neighbor] < 0 {
+                    continue;
+                }
+                match positions[neighbor] {
+                    None => {
+                        positions[neighbor] = Some((nx, ny));
+                        queue.push_back(neighbor);
+                    },
+                    Some((px, py)) => {
+                        if px != nx && spanning.horizontal.is_none() {
+                            spanning.horizontal = Some(label);
+                        }
+                        if py != ny && spanning.vertical.is_none() {
+                            spanning.vertical = Some(label);
+                        }
+                    },
+                }
+            }
+        }
+    }
+    spanning
+}
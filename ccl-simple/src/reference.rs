@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fmt;
+
+// labels `foreground` on the CPU with the classic two-pass union-find.
+// every component is labeled with the index of its first pixel like the shaders do,
+// the background with -1. `periodic` joins the opposite edges like wrap.comp.
+pub fn label(foreground: &[bool], width: usize, height: usize, connectivity: u32, periodic: bool) -> Vec<i32> {
+    let len = width * height;
+    let mut parents: Vec<usize> = (0..len).collect();
+    // the neighbors scanned before the pixel itself
+    let neighbors: &[(isize, isize)] = if connectivity == 8 {
+        &[(-1, 0), (-1, -1), (0, -1), (1, -1)]
+    } else {
+        &[(-1, 0), (0, -1)]
+    };
+    // first pass, joins every pixel with its preceding neighbors
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            if !foreground[index] {
+                continue;
+            }
+            for &(dx, dy) in neighbors {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx < 0 || ny < 0 || nx as usize >= width {
+                    continue;
+                }
+                let neighbor = ny as usize * width + nx as usize;
+                if foreground[neighbor] {
+                    union(&mut parents, index, neighbor);
+                }
+            }
+        }
+    }
+    // joins the pixels across the opposite edges, the same pairs as wrap.comp
+    if periodic {
+        let mut wrapped = vec![];
+        for y in 0..height {
+            wrapped.push(((width - 1, y), (0, y)));
+            if connectivity == 8 {
+                wrapped.push(((width - 1, y), (0, (y + height - 1) % height)));
+                wrapped.push(((width - 1, y), (0, (y + 1) % height)));
+            }
+        }
+        for x in 0..width {
+            wrapped.push(((x, height - 1), (x, 0)));
+            if connectivity == 8 {
+                wrapped.push(((x, height - 1), ((x + width - 1) % width, 0)));
+                wrapped.push(((x, height - 1), ((x + 1) % width, 0)));
+            }
+        }
+        for ((x0, y0), (x1, y1)) in wrapped {
+            let (index0, index1) = (y0 * width + x0, y1 * width + x1);
+            if foreground[index0] && foreground[index1] {
+                union(&mut parents, index0, index1);
+            }
+        }
+    }
+    // second pass, resolves every pixel to its root
+    (0..len)
+        .map(|index| if foreground[index] { find(&mut parents, index) as i32 } else { -1 })
+        .collect()
+}
+
+// the smaller root stays the root, so that the root is the first pixel of the component
+fn union(parents: &mut [usize], index0: usize, index1: usize) {
+    let root0 = find(parents, index0);
+    let root1 = find(parents, index1);
+    parents[root0.max(root1)] = root0.min(root1);
+}
+
+fn find(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    // path compression
+    let mut index = index;
+    while parents[index] != root {
+        let parent = parents[index];
+        parents[index] = root;
+        index = parent;
+    }
+    root
+}
+
+// the first pixel whose label disagrees with the reference
+pub struct Mismatch {
+    pub x: usize,
+    pub y: usize,
+    pub kind: MismatchKind,
+}
+
+pub enum MismatchKind {
+    // foreground in one labeling and background in the other
+    Background { label: i32, expected: i32 },
+    // the label covers two components of the reference
+    Merged { label: i32, expected: [i32; 2] },
+    // the component of the reference is covered by two labels
+    Split { labels: [i32; 2], expected: i32 },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pixel ({}, {}) ", self.x, self.y)?;
+        match self.kind {
+            MismatchKind::Background { label, expected } =>
+                write!(f, "is labeled {} where the reference has {}", label, expected),
+            MismatchKind::Merged { label, expected: [expected0, expected1] } =>
+                write!(f, "joins the components {} and {} of the reference as label {}", expected0, expected1, label),
+            MismatchKind::Split { labels: [label0, label1], expected } =>
+                write!(f, "is labeled {} where the rest of component {} of the reference is labeled {}", label1, expected, label0),
+        }
+    }
+}
+
+// checks that `labels` partitions the pixels in the same way as `reference`,
+// regardless of the values of the labels. returns the number of components.
+pub fn compare(labels: &[i32], reference: &[i32], width: usize) -> Result<usize, Mismatch> {
+    let mut forward: HashMap<i32, i32> = HashMap::new();
+    let mut backward: HashMap<i32, i32> = HashMap::new();
+    for (index, (&label, &expected)) in labels.iter().zip(reference).enumerate() {
+        let kind = if label < 0 || expected < 0 {
+            if label < 0 && expected < 0 {
+                continue;
+            }
+            MismatchKind::Background { label, expected }
+        } else {
+            let other = *forward.entry(label).or_insert(expected);
+            if other != expected {
+                MismatchKind::Merged { label, expected: [other, expected] }
+            } else {
+                let other = *backward.entry(expected).or_insert(label);
+                if other != label {
+                    MismatchKind::Split { labels: [other, label], expected }
+                } else {
+                    continue;
+                }
+            }
+        };
+        return Err(Mismatch { x: index % width, y: index / width, kind });
+    }
+    Ok(forward.len())
+}
@@ -0,0 +1,153 @@
+use filum::Context;
+
+use std::sync::Arc;
+
+use crate::masks::{self, Mask, Random, Shape};
+use crate::reference;
+
+// the tests label on a Vulkan device, a software driver such as Mesa's lavapipe will do.
+// without any device they pass after printing that they were skipped.
+fn context() -> Option<Arc<Context>> {
+    match Context::new() {
+        Ok(context) => Some(context),
+        Err(err) => {
+            eprintln!("skipped, no Vulkan device: {:?}", err);
+            None
+        },
+    }
+}
+
+// a few masks of every shape, noise of any density, spirals, checkerboards, serpentines,
+// full and empty images of up to 256x256 pixels
+fn suite() -> Vec<Mask> {
+    let mut random = Random::new(1);
+    let mut masks = vec![];
+    for _ in 0..4 {
+        let density = random.below(101) as f64 / 100.0;
+        let shapes = [
+            Shape::Noise(density),
+            Shape::Spiral,
+            Shape::Checkerboard,
+            Shape::Serpentine,
+            Shape::Full,
+            Shape::Empty,
+        ];
+        for &shape in shapes.iter() {
+            masks.push(masks::generate(shape, &mut random));
+        }
+    }
+    masks
+}
+
+fn table(foreground: &[bool]) -> Vec<i32> {
+    foreground.iter()
+        .enumerate()
+        .map(|(i, &v)| if v { i as i32 } else { -1 })
+        .collect()
+}
+
+// labels every mask of the suite and compares the labels with the CPU union-find
+fn check(connectivity: u32, periodic: bool) {
+    let context = match context() {
+        Some(context) => context,
+        None => return,
+    };
+    for mask in suite() {
+        let dim = (mask.width, mask.height);
+        let labels = crate::label(&context, &table(&mask.foreground), dim, connectivity, periodic, 1, None).unwrap();
+        let expected = reference::label(&mask.foreground, dim.0, dim.1, connectivity, periodic);
+        if let Err(mismatch) = reference::compare(&labels, &expected, dim.0) {
+            panic!("{} {}x{}, {}-connectivity{}: {}",
+                mask.shape, dim.0, dim.1, connectivity,
+                if periodic { ", periodic" } else { "" }, mismatch);
+        }
+    }
+}
+
+#[test]
+fn masks_4() {
+    check(4, false);
+}
+
+#[test]
+fn masks_8() {
+    check(8, false);
+}
+
+#[test]
+fn masks_4_periodic() {
+    check(4, true);
+}
+
+#[test]
+fn masks_8_periodic() {
+    check(8, true);
+}
+
+// the union-find of merge.comp must not depend on the order the invocations run in
+#[test]
+fn repeat() {
+    let context = match context() {
+        Some(context) => context,
+        None => return,
+    };
+    for mask in suite() {
+        let dim = (mask.width, mask.height);
+        for &connectivity in &[4, 8] {
+            let result = crate::label(&context, &table(&mask.foreground), dim, connectivity, false, 1000, None);
+            if let Err(message) = result {
+                panic!("{} {}x{}, {}-connectivity: {}", mask.shape, dim.0, dim.1, connectivity, message);
+            }
+        }
+    }
+}
+
+// labels a mask drawn with # for the foreground and . for the background,
+// returns the number of components
+fn count_components(context: &Arc<Context>, rows: &[&str], connectivity: u32) -> usize {
+    let dim = (rows[0].len(), rows.len());
+    let foreground: Vec<bool> = rows.concat().chars().map(|c| c == '#').collect();
+    let labels = crate::label(context, &table(&foreground), dim, connectivity, false, 1, None).unwrap();
+    let expected = reference::label(&foreground, dim.0, dim.1, connectivity, false);
+    reference::compare(&labels, &expected, dim.0).unwrap_or_else(|mismatch| panic!("{}", mismatch))
+}
+
+#[test]
+fn diagonal_chain() {
+    let context = match context() {
+        Some(context) => context,
+        None => return,
+    };
+    let rows = [
+        "#.......",
+        ".#......",
+        "..#.....",
+        "...#....",
+        "....#...",
+        ".....#..",
+        "......#.",
+        ".......#",
+    ];
+    assert_eq!(count_components(&context, &rows, 8), 1);
+    assert_eq!(count_components(&context, &rows, 4), 8);
+}
+
+// pixels which touch only diagonally across the boundary of the strips
+// of 8 columns, joined at the last merge step
+#[test]
+fn diagonal_across_strips() {
+    let context = match context() {
+        Some(context) => context,
+        None => return,
+    };
+    let rows = [
+        "................",
+        "......##........",
+        "........##......",
+        "................",
+        "........##......",
+        "......##........",
+    ];
+    assert_eq!(count_components(&context, &rows, 8), 2);
+    assert_eq!(count_components(&context, &rows, 4), 4);
+}
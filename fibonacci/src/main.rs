@@ -2,11 +2,16 @@
 extern crate filum;
 use filum::{Context, BufferViewBuilder, PipelineBuilder};
 
+use std::path::Path;
+
+mod shader;
+
 fn main() {
-    fibonacci(32);
+    let shader_dir = shader::dir_from_args();
+    fibonacci(32, shader_dir.as_deref());
 }
 
-fn fibonacci(num_elements: usize) {
+fn fibonacci(num_elements: usize, shader_dir: Option<&Path>) {
     // setup data to calculate fibonacci sequence
     let mut v: Vec<u32> = (0..num_elements as u32).collect();
     // filum automatically selects one from available GPUs. 
@@ -17,10 +22,10 @@ fn fibonacci(num_elements: usize) {
         .bind_array::<u32>(num_elements)
         .build()
         .unwrap();
-    // loads a compute shader from the specified file path
+    // loads a compute shader compiled by the build script
     // and associates it to the buffer.
     let pipeline = PipelineBuilder::new(buffer_view.buffer())
-        .shader("data/fibonacci.comp.spv")
+        .shader_bytes(shader::FIBONACCI.code(shader_dir))
         .build()
         .unwrap();
     // in order to transfer our data to the GPU, 
@@ -0,0 +1,46 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// SPIR-V module compiled from ./data by the build script
+pub struct Shader {
+    pub name: &'static str,
+    pub code: &'static [u8],
+}
+
+include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+
+impl Shader {
+    // returns the SPIR-V code to be passed to `PipelineBuilder::shader_bytes`.
+    // `dir` overrides the embedded module with `<dir>/<name>.comp.spv`.
+    pub fn code(&self, dir: Option<&Path>) -> Vec<u8> {
+        match dir {
+            Some(dir) => {
+                let path = dir.join(self.name);
+                fs::read(&path).unwrap_or_else(|err| {
+                    eprintln!("failed to read {}: {}", path.display(), err);
+                    process::exit(1);
+                })
+            },
+            None => self.code.to_vec(),
+        }
+    }
+}
+
+// parses `--shader-dir <DIR>` which points to SPIR-V modules compiled by hand,
+// so that shaders can be iterated on without rebuilding the example.
+pub fn dir_from_args() -> Option<PathBuf> {
+    let mut args = env::args().skip(1);
+    let mut dir = None;
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--shader-dir", Some(value)) => dir = Some(PathBuf::from(value)),
+            _ => {
+                eprintln!("usage: {} [--shader-dir <DIR>]", env!("CARGO_PKG_NAME"));
+                process::exit(1);
+            },
+        }
+    }
+    dir
+}
@@ -29,7 +29,7 @@ pub fn distance(ax: f32, ay: f32, az: f32, bx: f32, by: f32, bz: f32) -> f32 {
     let dx = bx - ax;
     let dy = by - ay;
     let dz = bz - az;
-    return (dx * dx + dy * dy + dz * dz).sqrt();
+    (dx * dx + dy * dy + dz * dz).sqrt()
 }
 
 #[inline]
@@ -37,12 +37,12 @@ fn cross(ax: f32, ay: f32, az: f32, bx: f32, by: f32, bz: f32) -> (f32, f32, f32
     let cx = ay * bz - by * az;
     let cy = az * bx - bz * ax;
     let cz = ax * by - bx * ay;
-    return (cx, cy, cz);
+    (cx, cy, cz)
 }
 
 #[inline]
 fn length(x: f32, y: f32, z: f32) -> f32 {
-    return (x * x + y * y + z * z).sqrt();
+    (x * x + y * y + z * z).sqrt()
 }
 
 #[repr(C)]
@@ -124,7 +124,7 @@ impl Vec3 {
             x: self.x, 
             y: self.y, 
             z: self.z, 
-            w: w,
+            w,
         }
     }
 }
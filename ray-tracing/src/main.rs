@@ -10,12 +10,14 @@ use rand::prelude::*;
 use std::time::Instant;
 
 mod geometry;
+mod shader;
 use geometry::*;
 
 // Ray Tracing in One Weekend
 // Copyright 2018-2019. Peter Shirley. All rights reserved.
 // @see https://raytracing.github.io/books/RayTracingInOneWeekend.html
 fn main() {
+    let shader_dir = shader::dir_from_args();
     let mut img_view = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::new(400, 200);
     let (width, height) = img_view.dimensions();
     let (width, height) = (width as usize, height as usize);
@@ -36,12 +38,12 @@ fn main() {
         .unwrap();
     let buffer = view.buffer();
     let sample = PipelineBuilder::new(buffer)
-        .shader("data/ray.comp.spv")
+        .shader_bytes(shader::RAY.code(shader_dir.as_deref()))
         .specialization(constants!(width as u32, height as u32, num_objects as u32))
         .build()
         .unwrap();
     let average = PipelineBuilder::new(buffer)
-        .shader("data/average.comp.spv")
+        .shader_bytes(shader::AVERAGE.code(shader_dir.as_deref()))
         .specialization(constants!(width as u32, num_samples as u32))
         .build()
         .unwrap();
@@ -187,7 +189,7 @@ fn main() {
             reserved0: 0,
             reserved1: 0,
             reserved2: 0,
-            camera: camera,
+            camera,
         };
         let dispatch_sample = DispatchBuilder::new(&sample)
             .workgroup_count(width, height, 1)
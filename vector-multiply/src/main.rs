@@ -3,7 +3,10 @@
 extern crate filum;
 use filum::{Context, BufferViewBuilder, PipelineBuilder};
 
+mod shader;
+
 fn main() {
+    let shader_dir = shader::dir_from_args();
     let num_elements = 128usize;
     let context = Context::new().unwrap();
     let buffer_view = BufferViewBuilder::new(&context)
@@ -17,7 +20,7 @@ fn main() {
         .unwrap();
     let buffer = buffer_view.buffer();
     let pipeline = PipelineBuilder::new(buffer)
-        .shader("data/multiply.comp.spv")
+        .shader_bytes(shader::MULTIPLY.code(shader_dir.as_deref()))
         .build()
         .unwrap();
     let lhs = buffer_view.first_binding();